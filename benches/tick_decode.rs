@@ -0,0 +1,135 @@
+//! Binary tick decoding: `decode_binary_ticks` (Vec<Tick> per frame) against
+//! the allocation-free `visit_binary_ticks`, and applying a frame to the store
+//! through owned ticks against `TickStore::update_packet`.
+//!
+//! Run with `cargo bench --bench tick_decode`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use zatamap_trade_rust::ticks::encode::encode_frame;
+use zatamap_trade_rust::ticks::history::TickHistoryConfig;
+use zatamap_trade_rust::ticks::{
+    decode_binary_ticks, visit_binary_ticks, DepthLevel, MarketDepth, Ohlc, Tick, TickMode,
+    TickStore, TokenMeta,
+};
+
+/// FULL tick with a plausible option quote and 5x2 depth.
+fn full_tick(token: i32, ltp: f64) -> Tick {
+    let level = |side: f64, i: usize| DepthLevel {
+        quantity: (i as u32 + 1) * 75,
+        price: ltp + side * 0.05 * (i + 1) as f64,
+        orders: i as u16 + 1,
+    };
+    Tick {
+        mode: TickMode::Full,
+        last_quantity: Some(75),
+        average_traded_price: Some(ltp - 0.4),
+        volume_traded: Some(1_250_000),
+        total_buy_quantity: Some(610_000),
+        total_sell_quantity: Some(640_000),
+        ohlc: Some(Ohlc {
+            open: ltp - 5.0,
+            high: ltp + 9.0,
+            low: ltp - 12.0,
+            close: ltp - 3.0,
+        }),
+        last_trade_time: Some(1_718_000_000),
+        open_interest: Some(4_200_000),
+        oi_day_high: Some(4_350_000),
+        oi_day_low: Some(4_100_000),
+        exchange_timestamp: Some(1_718_000_001),
+        depth: Some(MarketDepth {
+            buy: std::array::from_fn(|i| level(-1.0, i)),
+            sell: std::array::from_fn(|i| level(1.0, i)),
+        }),
+        ..Tick::new_ltp(token, ltp, 0)
+    }
+}
+
+/// One frame the way Kite batches an option chain: two indices (32-byte
+/// index packets) plus `options` FULL packets.
+fn chain_frame(options: i32) -> (Vec<u8>, Vec<i32>) {
+    let mut ticks = vec![full_tick(256265, 23465.6), full_tick(260105, 50002.3)];
+    for i in 0..options {
+        // NFO tokens (segment 2).
+        ticks.push(full_tick(((48_000 + i) << 8) | 2, 182.4 + i as f64 * 0.35));
+    }
+    let tokens = ticks.iter().map(|t| t.instrument_token).collect();
+    (encode_frame(&ticks), tokens)
+}
+
+fn seeded_store(tokens: &[i32]) -> TickStore {
+    // History on, as in the ticker.
+    let store = TickStore::default().with_history(TickHistoryConfig::default());
+    store.seed_meta(
+        tokens
+            .iter()
+            .map(|t| TokenMeta::new(*t, format!("SYM{t}"), "CE", None::<&str>, None)),
+    );
+    store
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_frame");
+    for options in [10, 50, 200] {
+        let (frame, _) = chain_frame(options);
+        group.throughput(Throughput::Bytes(frame.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("decode_binary_ticks", options),
+            &frame,
+            |b, f| b.iter(|| decode_binary_ticks(black_box(f), 0)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("visit_to_tick", options),
+            &frame,
+            |b, f| {
+                b.iter(|| {
+                    let mut volume = 0u64;
+                    visit_binary_ticks(black_box(f), |p| {
+                        volume += p.to_tick(0).volume_traded.unwrap_or(0) as u64;
+                    });
+                    volume
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("visit_ltp_only", options),
+            &frame,
+            |b, f| {
+                b.iter(|| {
+                    let mut sum = 0.0;
+                    visit_binary_ticks(black_box(f), |p| sum += p.last_price());
+                    sum
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_store(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_frame_to_store");
+    let (frame, tokens) = chain_frame(50);
+    let store = seeded_store(&tokens);
+    group.throughput(Throughput::Bytes(frame.len() as u64));
+
+    group.bench_function("decode_binary_ticks", |b| {
+        b.iter(|| {
+            for t in decode_binary_ticks(black_box(&frame), 1) {
+                store.update_tick(t);
+            }
+        })
+    });
+    // The ticker's previous hot path: an owned tick per packet, moved into the store.
+    group.bench_function("visit_to_tick", |b| {
+        b.iter(|| visit_binary_ticks(black_box(&frame), |p| store.update_tick(p.to_tick(1))))
+    });
+    // The current one: packet fields written into the stored tick in place.
+    group.bench_function("visit_update_packet", |b| {
+        b.iter(|| visit_binary_ticks(black_box(&frame), |p| store.update_packet(&p, 1)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode, bench_store);
+criterion_main!(benches);
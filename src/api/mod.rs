@@ -10,3 +10,12 @@ pub fn router(state: AppState) -> Router {
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
+
+/// Only `/api/health`, for the ticker process (`TICKER_HTTP_ADDR`), which
+/// must not expose the login callback or account endpoints.
+pub fn health_router(state: AppState) -> Router {
+    Router::new()
+        .merge(routes::health_router())
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
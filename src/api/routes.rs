@@ -1,21 +1,58 @@
-use axum::{routing::get, Router};
+use axum::{http::StatusCode, routing::get, Router};
+
+use crate::core::AppError;
+use crate::kite::error::KiteError;
 
 pub fn router() -> Router<crate::core::AppState> {
-    Router::new()
-        .route("/api/health", get(health::health))
+    health_router()
         .route("/api/kite/login_url", get(kite::login_url))
         .route("/api/kite/callback", get(kite::callback))
+        .route("/api/positions", get(portfolio::positions))
+}
+
+/// `/api/health` only.
+pub fn health_router() -> Router<crate::core::AppState> {
+    Router::new().route("/api/health", get(health::health))
+}
+
+/// Response status for a failed Kite call: 4xx when Kite rejected the
+/// caller's session or request, 502 when Kite or the network failed.
+fn kite_error_status(e: &AppError) -> StatusCode {
+    match e.kite_error() {
+        Some(KiteError::Token { .. }) => StatusCode::UNAUTHORIZED,
+        Some(KiteError::Permission { .. }) => StatusCode::FORBIDDEN,
+        Some(KiteError::Input { .. } | KiteError::Order { .. }) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::BAD_GATEWAY,
+    }
 }
 
 mod health {
     use axum::{extract::State, Json};
     use serde_json::json;
+    use std::time::Duration;
 
-    use crate::core::AppState;
+    use crate::{core::AppState, kite::ws_pool::FeedHealth};
+
+    /// How old a `TICKER_HEALTH_FILE` snapshot may be before the feed counts
+    /// as down; the ticker rewrites it every 2s.
+    const SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(10);
 
     pub async fn health(State(state): State<AppState>) -> Json<serde_json::Value> {
         let db_ok = state.db.health().await.unwrap_or(false);
-        Json(json!({"status": "ok", "db": db_ok}))
+        // `feed` comes from the ticker in this process, else from the snapshot
+        // the ticker process writes; null when neither is configured.
+        let feed = match (&state.ticker, &state.config.ticker_health_file) {
+            (Some(pool), _) => Some(pool.feed_health()),
+            (None, Some(path)) => Some(FeedHealth::read_snapshot(path, SNAPSHOT_MAX_AGE)),
+            (None, None) => None,
+        };
+        let feed_alive = feed.as_ref().map(|f| f.alive);
+        let status = if feed_alive == Some(false) {
+            "degraded"
+        } else {
+            "ok"
+        };
+        Json(json!({"status": status, "db": db_ok, "feed": feed}))
     }
 }
 
@@ -44,16 +81,19 @@ mod kite {
         State(state): State<AppState>,
         Query(q): Query<CallbackQuery>,
     ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-        let user_id = q.user_id.or(q.userid)
-            .ok_or((StatusCode::BAD_REQUEST, "Missing user_id/userid".to_string()))?;
+        let user_id = q.user_id.or(q.userid).ok_or((
+            StatusCode::BAD_REQUEST,
+            "Missing user_id/userid".to_string(),
+        ))?;
 
-        let creds = profile_dao::get_user_kite_creds_for_os(&state.db, &user_id, &state.config.os_type)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let creds =
+            profile_dao::get_user_kite_creds_for_os(&state.db, &user_id, &state.config.os_type)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
         let creds = creds.ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
         let callback_url = auth::callback_url_for_user(&state.config.kite_callback_url, &user_id);
-        let url = auth::login_url(&creds.api_key, &callback_url);
+        let url = auth::login_url(&state.config.kite.login_url, &creds.api_key, &callback_url);
         Ok(Json(json!({"login_url": url})))
     }
 
@@ -75,22 +115,29 @@ mod kite {
                 ));
             }
         }
-        let user_id = q.user_id.or(q.userid)
-            .ok_or((StatusCode::BAD_REQUEST, "Missing user_id/userid".to_string()))?;
+        let user_id = q.user_id.or(q.userid).ok_or((
+            StatusCode::BAD_REQUEST,
+            "Missing user_id/userid".to_string(),
+        ))?;
 
         let request_token = q
             .request_token
             .ok_or((StatusCode::BAD_REQUEST, "Missing request_token".to_string()))?;
 
-        let creds = profile_dao::get_user_kite_creds_for_os(&state.db, &user_id, &state.config.os_type)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let creds =
+            profile_dao::get_user_kite_creds_for_os(&state.db, &user_id, &state.config.os_type)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
         let creds = creds.ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
 
-        let session =
-            auth::exchange_request_token(&creds.api_key, &creds.api_secret, &request_token)
-                .await
-                .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+        let session = auth::exchange_request_token(
+            &state.config.kite.api_base_url,
+            &creds.api_key,
+            &creds.api_secret,
+            &request_token,
+        )
+        .await
+        .map_err(|e| (super::kite_error_status(&e), e.to_string()))?;
 
         let updated = profile_dao::update_session_tokens_for_os(
             &state.db,
@@ -100,8 +147,8 @@ mod kite {
             &session.access_token,
             session.public_token.as_deref(),
         )
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
         if updated == 0 {
             return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
         }
@@ -114,3 +161,78 @@ mod kite {
         })))
     }
 }
+
+mod portfolio {
+    use axum::{
+        extract::{Query, State},
+        http::StatusCode,
+        Json,
+    };
+    use serde::Deserialize;
+
+    use crate::{
+        core::AppState, dao::profile_dao, kite::client::KiteClient, kite::types::Positions,
+    };
+
+    #[derive(Debug, Deserialize)]
+    pub struct UserQuery {
+        pub user_id: Option<String>,
+        #[serde(rename = "userid")]
+        pub userid: Option<String>,
+    }
+
+    /// Net + day positions for a user, using the access token stored in `trade.profile`.
+    pub async fn positions(
+        State(state): State<AppState>,
+        Query(q): Query<UserQuery>,
+    ) -> Result<Json<Positions>, (StatusCode, String)> {
+        let user_id = q.user_id.or(q.userid).ok_or((
+            StatusCode::BAD_REQUEST,
+            "Missing user_id/userid".to_string(),
+        ))?;
+
+        let creds =
+            profile_dao::get_user_kite_creds_for_os(&state.db, &user_id, &state.config.os_type)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let creds = creds.ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+        let access_token = creds.access_token.filter(|s| !s.trim().is_empty()).ok_or((
+            StatusCode::UNAUTHORIZED,
+            "No access_token for user (run autologin first)".to_string(),
+        ))?;
+
+        let kite = KiteClient::with_base_url(
+            &creds.api_key,
+            access_token.trim(),
+            &state.config.kite.api_base_url,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let positions = kite
+            .positions()
+            .await
+            .map_err(|e| (super::kite_error_status(&e), e.to_string()))?;
+        Ok(Json(positions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kite_errors_map_to_client_or_gateway_statuses() {
+        let status = |error_type: &str| {
+            let e = AppError::from(KiteError::new(Some(error_type), 403, "x".to_string()));
+            kite_error_status(&e)
+        };
+        assert_eq!(status("TokenException"), StatusCode::UNAUTHORIZED);
+        assert_eq!(status("PermissionException"), StatusCode::FORBIDDEN);
+        assert_eq!(status("InputException"), StatusCode::BAD_REQUEST);
+        assert_eq!(status("OrderException"), StatusCode::BAD_REQUEST);
+        assert_eq!(status("NetworkException"), StatusCode::BAD_GATEWAY);
+        assert_eq!(status("GeneralException"), StatusCode::BAD_GATEWAY);
+        assert_eq!(status("DataException"), StatusCode::BAD_GATEWAY);
+        let other = AppError::KiteApi("Missing data in response".to_string());
+        assert_eq!(kite_error_status(&other), StatusCode::BAD_GATEWAY);
+    }
+}
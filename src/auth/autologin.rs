@@ -84,7 +84,7 @@ pub async fn maybe_autologin_for_os(
     let redirect_url = auth::callback_url_for_user(&state.config.kite_callback_url, user_id);
     // This mirrors your Python function `login_and_get_tokens`.
     // Important: we pass the per-user redirect_url into Kite login so the final redirect contains it.
-    let kite_connect_login_url = auth::login_url(&login.api_key, &redirect_url);
+    let kite_connect_login_url = auth::login_url(&state.config.kite.login_url, &login.api_key, &redirect_url);
     info!(user_id = user_id, os_type = effective_os, headless = headless, debug = options.debug, redirect_url = %redirect_url, "autologin start");
 
     let mut spawned: Option<Child> = None;
@@ -169,7 +169,7 @@ pub async fn maybe_autologin_for_os(
     }
     let request_token = result?;
 
-    let session = auth::exchange_request_token(&login.api_key, &login.api_secret, &request_token).await?;
+    let session = auth::exchange_request_token(&state.config.kite.api_base_url, &login.api_key, &login.api_secret, &request_token).await?;
     let updated = profile_dao::update_session_tokens_for_os(
         &state.db,
         user_id,
@@ -189,7 +189,7 @@ pub async fn maybe_autologin_for_os(
     // Mirror the Python flow: after successful login, refresh instruments into Postgres.
     info!(user_id = user_id, "housekeeping start (refresh trade.instrument)");
     let housekeeping_started = std::time::Instant::now();
-    let kite = crate::kite::client::KiteClient::new(&login.api_key, &session.access_token)?;
+    let kite = crate::kite::client::KiteClient::with_base_url(&login.api_key, &session.access_token, &state.config.kite.api_base_url)?;
     let n = crate::instruments::refresh_trade_instruments(&state.db, &kite).await?;
     info!(user_id = user_id, refreshed_rows = n, elapsed_ms = housekeeping_started.elapsed().as_millis() as u64, "housekeeping done");
     Ok(())
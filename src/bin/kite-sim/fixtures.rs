@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::path::Path;
+use zatamap_trade_rust::core::AppError;
+
+/// Fixture files served by the simulator.
+///
+/// Layout of the fixtures directory:
+/// - `profile.json`: `data` of `GET /user/profile`
+/// - `holdings.json`: `data` of `GET /portfolio/holdings`
+/// - `session.json`: `data` of `POST /session/token`
+/// - `instruments.csv`: body of `GET /instruments`; also seeds ticker prices
+pub struct Fixtures {
+    pub profile: serde_json::Value,
+    pub holdings: serde_json::Value,
+    pub session: serde_json::Value,
+    pub instruments_csv: String,
+    /// instrument_token -> instrument row (for ticker price seeding).
+    pub instruments: HashMap<i32, FixtureInstrument>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct FixtureInstrument {
+    pub instrument_token: i32,
+    #[serde(default)]
+    pub tradingsymbol: String,
+    #[serde(default)]
+    pub last_price: Option<f64>,
+    #[serde(default)]
+    pub segment: Option<String>,
+}
+
+impl Fixtures {
+    pub fn load(dir: &Path) -> Result<Self, AppError> {
+        let read = |name: &str| {
+            std::fs::read_to_string(dir.join(name)).map_err(|e| {
+                AppError::KiteApi(format!(
+                    "kite-sim fixture {}: {e}",
+                    dir.join(name).display()
+                ))
+            })
+        };
+
+        let instruments_csv = read("instruments.csv")?;
+        let mut instruments = HashMap::new();
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(instruments_csv.as_bytes());
+        for rec in rdr.deserialize() {
+            let row: FixtureInstrument = rec?;
+            instruments.insert(row.instrument_token, row);
+        }
+
+        Ok(Self {
+            profile: serde_json::from_str(&read("profile.json")?)?,
+            holdings: serde_json::from_str(&read("holdings.json")?)?,
+            session: serde_json::from_str(&read("session.json")?)?,
+            instruments_csv,
+            instruments,
+        })
+    }
+}
@@ -0,0 +1,132 @@
+//! Offline Kite Connect simulator.
+//!
+//! Serves the REST endpoints this crate uses from fixture files, plus a ticker
+//! websocket that emits binary frames built with `ticks::encode` (the layout
+//! `decode_binary_ticks` parses).
+//! Point the main binary at it with:
+//!
+//! ```text
+//! KITE_API_BASE_URL=http://127.0.0.1:9000
+//! KITE_WS_URL=ws://127.0.0.1:9000/ws
+//! KITE_LOGIN_URL=http://127.0.0.1:9000/connect/login
+//! ```
+
+mod fixtures;
+mod rest;
+mod ticker;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use fixtures::Fixtures;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::trace::TraceLayer;
+use tracing::info;
+use zatamap_trade_rust::core::AppError;
+
+fn usage() -> &'static str {
+    r#"Usage:
+    cargo run --bin kite-sim
+
+Env:
+    KITE_SIM_ADDR (default 127.0.0.1:9000)
+    KITE_SIM_FIXTURES (default fixtures/kite-sim)
+    KITE_SIM_ACCESS_TOKEN (if set, only this access_token is accepted; others get TokenException/403)
+    KITE_SIM_TICK_INTERVAL_MS (default 1000; ticker frame interval)
+    KITE_SIM_REDIRECT_URL (where /connect/login redirects when no redirect_url is given)
+"#
+}
+
+/// Shared simulator state.
+pub struct SimState {
+    pub fixtures: Fixtures,
+    /// When set, REST and websocket reject every other access token.
+    pub access_token: Option<String>,
+    pub tick_interval: Duration,
+    /// Registered app redirect URL (Kite keeps this per API key).
+    pub redirect_url: Option<String>,
+}
+
+impl SimState {
+    /// Kite semantics: a missing or unknown token is a `TokenException`.
+    pub fn token_ok(&self, access_token: Option<&str>) -> bool {
+        match (&self.access_token, access_token) {
+            (_, None) => false,
+            (None, Some(t)) => !t.is_empty(),
+            (Some(expected), Some(t)) => expected == t,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), AppError> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .compact()
+        .init();
+
+    if std::env::args().skip(1).any(|a| a == "-h" || a == "--help") {
+        println!("{}", usage());
+        return Ok(());
+    }
+
+    let addr = std::env::var("KITE_SIM_ADDR").unwrap_or_else(|_| "127.0.0.1:9000".into());
+    let addr: std::net::SocketAddr = addr
+        .parse()
+        .map_err(|e| AppError::KiteApi(format!("Invalid KITE_SIM_ADDR: {e}")))?;
+    let fixtures_dir =
+        std::env::var("KITE_SIM_FIXTURES").unwrap_or_else(|_| "fixtures/kite-sim".into());
+    let tick_interval_ms = std::env::var("KITE_SIM_TICK_INTERVAL_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(1000);
+
+    let fixtures = Fixtures::load(std::path::Path::new(&fixtures_dir))?;
+    info!(
+        dir = %fixtures_dir,
+        instruments = fixtures.instruments.len(),
+        "kite-sim fixtures loaded"
+    );
+
+    let state = Arc::new(SimState {
+        fixtures,
+        access_token: std::env::var("KITE_SIM_ACCESS_TOKEN")
+            .ok()
+            .filter(|s| !s.trim().is_empty()),
+        tick_interval: Duration::from_millis(tick_interval_ms),
+        redirect_url: std::env::var("KITE_SIM_REDIRECT_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty()),
+    });
+
+    let app = Router::new()
+        .route("/user/profile", get(rest::profile))
+        .route("/portfolio/holdings", get(rest::holdings))
+        .route("/instruments", get(rest::instruments))
+        .route(
+            "/session/token",
+            post(rest::session_token).delete(rest::invalidate_token),
+        )
+        .route("/connect/login", get(rest::login))
+        // The client connects to `{KITE_WS_URL}/?api_key=...`, so accept both forms.
+        .route("/", get(ticker::upgrade))
+        .route("/ws", get(ticker::upgrade))
+        .route("/ws/", get(ticker::upgrade))
+        .fallback(rest::not_found)
+        .layer(TraceLayer::new_for_http())
+        .with_state(state);
+
+    info!(addr = %addr, "kite-sim listening");
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .map_err(|e| AppError::KiteApi(e.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::SimState;
+use axum::{
+    extract::{Form, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+type Shared = State<Arc<SimState>>;
+
+const SIM_REQUEST_TOKEN: &str = "sim-request-token";
+
+fn success(data: serde_json::Value) -> Response {
+    Json(json!({"status": "success", "data": data})).into_response()
+}
+
+fn error(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "status": "error",
+            "message": message,
+            "data": null,
+            "error_type": error_type,
+        })),
+    )
+        .into_response()
+}
+
+/// Access token from `Authorization: token <api_key>:<access_token>`.
+fn access_token(headers: &HeaderMap) -> Option<&str> {
+    let auth = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let creds = auth.strip_prefix("token ")?;
+    creds.split_once(':').map(|(_, token)| token)
+}
+
+/// Reject the request the way Kite does when the session is missing/expired.
+fn unauthorized(state: &SimState, headers: &HeaderMap) -> Option<Response> {
+    if state.token_ok(access_token(headers)) {
+        None
+    } else {
+        Some(error(
+            StatusCode::FORBIDDEN,
+            "TokenException",
+            "Incorrect `api_key` or `access_token`.",
+        ))
+    }
+}
+
+pub async fn profile(State(state): Shared, headers: HeaderMap) -> Response {
+    if let Some(resp) = unauthorized(&state, &headers) {
+        return resp;
+    }
+    success(state.fixtures.profile.clone())
+}
+
+pub async fn holdings(State(state): Shared, headers: HeaderMap) -> Response {
+    if let Some(resp) = unauthorized(&state, &headers) {
+        return resp;
+    }
+    success(state.fixtures.holdings.clone())
+}
+
+/// Not an envelope endpoint: Kite returns the raw CSV dump.
+pub async fn instruments(State(state): Shared, headers: HeaderMap) -> Response {
+    if let Some(resp) = unauthorized(&state, &headers) {
+        return resp;
+    }
+    (
+        [(header::CONTENT_TYPE, "text/csv")],
+        state.fixtures.instruments_csv.clone(),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionForm {
+    pub api_key: Option<String>,
+    pub request_token: Option<String>,
+    pub checksum: Option<String>,
+}
+
+pub async fn session_token(State(state): Shared, Form(form): Form<SessionForm>) -> Response {
+    let present = |v: &Option<String>| v.as_deref().map(|s| !s.is_empty()).unwrap_or(false);
+    if !present(&form.api_key) || !present(&form.request_token) || !present(&form.checksum) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "InputException",
+            "Missing api_key, request_token or checksum",
+        );
+    }
+    success(state.fixtures.session.clone())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvalidateQuery {
+    pub access_token: Option<String>,
+}
+
+pub async fn invalidate_token(State(state): Shared, Query(q): Query<InvalidateQuery>) -> Response {
+    if !state.token_ok(q.access_token.as_deref()) {
+        return error(
+            StatusCode::FORBIDDEN,
+            "TokenException",
+            "Incorrect `api_key` or `access_token`.",
+        );
+    }
+    success(json!(true))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginQuery {
+    pub api_key: Option<String>,
+    pub redirect_url: Option<String>,
+}
+
+/// Stand-in for the Kite login page: skips credentials and redirects straight
+/// to the app's redirect URL with a fixed `request_token`.
+pub async fn login(State(state): Shared, Query(q): Query<LoginQuery>) -> Response {
+    if q.api_key.as_deref().map(str::is_empty).unwrap_or(true) {
+        return error(StatusCode::BAD_REQUEST, "InputException", "Missing api_key");
+    }
+    let Some(redirect) = q.redirect_url.or_else(|| state.redirect_url.clone()) else {
+        return success(json!({"request_token": SIM_REQUEST_TOKEN}));
+    };
+    let sep = if redirect.contains('?') { '&' } else { '?' };
+    Redirect::to(&format!(
+        "{redirect}{sep}action=login&type=login&status=success&request_token={SIM_REQUEST_TOKEN}"
+    ))
+    .into_response()
+}
+
+pub async fn not_found() -> impl IntoResponse {
+    error(StatusCode::NOT_FOUND, "GeneralException", "Route not found")
+}
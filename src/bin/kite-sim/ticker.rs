@@ -0,0 +1,241 @@
+use crate::SimState;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use zatamap_trade_rust::ticks::encode::encode_frame;
+use zatamap_trade_rust::ticks::{now_unix_ns, DepthLevel, MarketDepth, Ohlc, Tick, TickMode};
+
+#[derive(Debug, Deserialize)]
+pub struct WsQuery {
+    pub api_key: Option<String>,
+    pub access_token: Option<String>,
+}
+
+/// Websocket handshake. Like Kite, an invalid token fails the HTTP upgrade with 403.
+pub async fn upgrade(
+    State(state): State<Arc<SimState>>,
+    Query(q): Query<WsQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if q.api_key.as_deref().map(str::is_empty).unwrap_or(true)
+        || !state.token_ok(q.access_token.as_deref())
+    {
+        return (StatusCode::FORBIDDEN, "invalid api_key or access_token").into_response();
+    }
+    ws.on_upgrade(move |socket| session(socket, state))
+}
+
+/// Control message sent by ticker clients: `{"a": "subscribe", "v": [...]}`.
+#[derive(Debug, Deserialize)]
+struct Control {
+    a: String,
+    v: serde_json::Value,
+}
+
+/// Simulated market state for one instrument (prices in rupees).
+#[derive(Debug, Clone)]
+struct SimInstrument {
+    ltp: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u32,
+    oi: u32,
+}
+
+impl SimInstrument {
+    fn new(base: f64) -> Self {
+        Self {
+            ltp: base,
+            open: base,
+            high: base,
+            low: base,
+            close: base,
+            volume: 0,
+            oi: 100_000,
+        }
+    }
+
+    /// Random walk of up to ±0.1% per tick, rounded to the 0.05 tick size.
+    fn step(&mut self, rng: &mut XorShift) {
+        let pct = (rng.next_f64() - 0.5) * 0.002;
+        let next = ((self.ltp * (1.0 + pct)) * 20.0).round() / 20.0;
+        self.ltp = next.max(0.05);
+        self.high = self.high.max(self.ltp);
+        self.low = self.low.min(self.ltp);
+        self.volume = self
+            .volume
+            .saturating_add(1 + (rng.next_u64() % 500) as u32);
+        self.oi = self
+            .oi
+            .saturating_add((rng.next_u64() % 200) as u32)
+            .saturating_sub(100);
+    }
+}
+
+/// Tiny deterministic PRNG; the simulator only needs plausible noise.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Per-connection ticker session: applies subscribe/unsubscribe/mode messages
+/// and streams one binary frame per interval for the current subscriptions.
+async fn session(mut socket: WebSocket, state: Arc<SimState>) {
+    info!("kite-sim ticker client connected");
+    let mut modes: HashMap<i32, TickMode> = HashMap::new();
+    let mut market: HashMap<i32, SimInstrument> = HashMap::new();
+    let mut rng = XorShift(now_unix_ns() | 1);
+    let mut interval = tokio::time::interval(state.tick_interval);
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Text(txt))) => apply_control(&txt, &mut modes),
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!(error = %e, "kite-sim ticker read error");
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                // Kite sends a 1-byte heartbeat when there is nothing to stream.
+                let frame = if modes.is_empty() {
+                    vec![0u8]
+                } else {
+                    let mut ticks: Vec<Tick> = Vec::with_capacity(modes.len());
+                    for (&token, &mode) in &modes {
+                        let inst = market.entry(token).or_insert_with(|| {
+                            let base = state
+                                .fixtures
+                                .instruments
+                                .get(&token)
+                                .and_then(|i| i.last_price)
+                                .filter(|p| *p > 0.0)
+                                .unwrap_or(100.0);
+                            SimInstrument::new(base)
+                        });
+                        inst.step(&mut rng);
+                        ticks.push(sim_tick(token, mode, inst));
+                    }
+                    encode_frame(&ticks)
+                };
+                if socket.send(Message::Binary(frame)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    info!("kite-sim ticker client disconnected");
+}
+
+fn apply_control(txt: &str, modes: &mut HashMap<i32, TickMode>) {
+    let Ok(ctl) = serde_json::from_str::<Control>(txt) else {
+        warn!(message = %txt, "kite-sim ignoring malformed control message");
+        return;
+    };
+    let tokens = |v: &serde_json::Value| -> Vec<i32> {
+        v.as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|t| t.as_i64())
+                    .map(|t| t as i32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    match ctl.a.as_str() {
+        "subscribe" => {
+            // Kite subscribes in quote mode until told otherwise.
+            for t in tokens(&ctl.v) {
+                modes.entry(t).or_insert(TickMode::Quote);
+            }
+        }
+        "unsubscribe" => {
+            for t in tokens(&ctl.v) {
+                modes.remove(&t);
+            }
+        }
+        "mode" => {
+            let raw = ctl.v.get(0).and_then(|m| m.as_str()).unwrap_or("");
+            let mode = match raw.parse::<TickMode>() {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!(error = %e, "kite-sim ignoring unknown mode");
+                    return;
+                }
+            };
+            for t in ctl.v.get(1).map(tokens).unwrap_or_default() {
+                if let Some(m) = modes.get_mut(&t) {
+                    *m = mode;
+                }
+            }
+        }
+        other => debug!(action = other, "kite-sim ignoring control action"),
+    }
+}
+
+/// Tick for `s` in `mode`. Every field is filled; `encode_frame` writes the
+/// ones Kite's layout for the mode (and index tokens) carries.
+fn sim_tick(token: i32, mode: TickMode, s: &SimInstrument) -> Tick {
+    let now_s = (now_unix_ns() / 1_000_000_000) as u32;
+    // 5 bid levels below LTP, then 5 ask levels above it, 0.05 apart.
+    let level = |side: f64, i: usize| DepthLevel {
+        quantity: (i as u32 + 1) * 50,
+        price: (s.ltp + side * 0.05 * (i + 1) as f64).max(0.05),
+        orders: i as u16 + 1,
+    };
+    Tick {
+        instrument_token: token,
+        mode,
+        last_price: s.ltp,
+        last_quantity: Some(25),
+        average_traded_price: Some((s.high + s.low + s.ltp) / 3.0),
+        volume_traded: Some(s.volume),
+        total_buy_quantity: Some(s.volume / 2 + 1_000),
+        total_sell_quantity: Some(s.volume / 2 + 1_200),
+        ohlc: Some(Ohlc {
+            open: s.open,
+            high: s.high,
+            low: s.low,
+            close: s.close,
+        }),
+        // Index packets carry the absolute change.
+        change: Some(s.ltp - s.close),
+        last_trade_time: Some(now_s),
+        open_interest: Some(s.oi),
+        oi_day_high: Some(s.oi.saturating_add(5_000)),
+        oi_day_low: Some(s.oi.saturating_sub(5_000)),
+        exchange_timestamp: Some(now_s),
+        depth: Some(MarketDepth {
+            buy: std::array::from_fn(|i| level(-1.0, i)),
+            sell: std::array::from_fn(|i| level(1.0, i)),
+        }),
+        received_ns: 0,
+    }
+}
@@ -1,11 +1,57 @@
 use super::error::AppError;
 
+/// Kite hosts. Defaults point at production; override to target a local simulator.
+#[derive(Clone, Debug)]
+pub struct KiteEndpoints {
+    /// REST base, e.g. `https://api.kite.trade`
+    pub api_base_url: String,
+    /// Ticker websocket, e.g. `wss://ws.kite.trade`
+    pub ws_url: String,
+    /// Connect login page, e.g. `https://kite.zerodha.com/connect/login`
+    pub login_url: String,
+}
+
+impl Default for KiteEndpoints {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://api.kite.trade".to_string(),
+            ws_url: "wss://ws.kite.trade".to_string(),
+            login_url: "https://kite.zerodha.com/connect/login".to_string(),
+        }
+    }
+}
+
+impl KiteEndpoints {
+    /// Env (all optional):
+    /// - KITE_API_BASE_URL (default https://api.kite.trade)
+    /// - KITE_WS_URL (default wss://ws.kite.trade)
+    /// - KITE_LOGIN_URL (default https://kite.zerodha.com/connect/login)
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let env_url = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .map(|v| v.trim().trim_end_matches('/').to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            api_base_url: env_url("KITE_API_BASE_URL").unwrap_or(d.api_base_url),
+            ws_url: env_url("KITE_WS_URL").unwrap_or(d.ws_url),
+            login_url: env_url("KITE_LOGIN_URL").unwrap_or(d.login_url),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub server_addr: String,
     pub database_url: String,
     pub kite_callback_url: String,
     pub os_type: String,
+    pub kite: KiteEndpoints,
+    /// Feed health snapshot shared between the ticker (writer) and the server
+    /// (`/api/health` reader); `TICKER_HEALTH_FILE`.
+    pub ticker_health_file: Option<std::path::PathBuf>,
 
     // Startup auto-login (initialize_on_startup equivalent)
     pub startup_autologin_user_id: Option<String>,
@@ -48,7 +94,8 @@ impl AppConfig {
         //
         // Supported values (recommended): macos | ubuntu
         // Fallback: derived from runtime OS.
-        let os_type = std::env::var("OS_TYPE").unwrap_or_else(|_| normalize_os(std::env::consts::OS));
+        let os_type =
+            std::env::var("OS_TYPE").unwrap_or_else(|_| normalize_os(std::env::consts::OS));
 
         // Startup autologin controls (mirrors Python initialize_on_startup knobs).
         // If STARTUP_AUTOLOGIN_USER_ID is set, startup will attempt selenium autologin.
@@ -67,6 +114,8 @@ impl AppConfig {
             database_url,
             kite_callback_url,
             os_type,
+            kite: KiteEndpoints::from_env(),
+            ticker_health_file: ticker_health_file_from_env(),
 
             startup_autologin_user_id,
             startup_autologin_os_type,
@@ -105,7 +154,8 @@ impl AppConfig {
         });
 
         let kite_callback_url = std::env::var("KITE_CALLBACK_URL").unwrap_or_default();
-        let os_type = std::env::var("OS_TYPE").unwrap_or_else(|_| normalize_os(std::env::consts::OS));
+        let os_type =
+            std::env::var("OS_TYPE").unwrap_or_else(|_| normalize_os(std::env::consts::OS));
 
         let startup_autologin_user_id = std::env::var("STARTUP_AUTOLOGIN_USER_ID")
             .ok()
@@ -122,6 +172,8 @@ impl AppConfig {
             database_url,
             kite_callback_url,
             os_type,
+            kite: KiteEndpoints::from_env(),
+            ticker_health_file: ticker_health_file_from_env(),
             startup_autologin_user_id,
             startup_autologin_os_type,
             startup_autologin_debug,
@@ -130,13 +182,24 @@ impl AppConfig {
     }
 }
 
+fn ticker_health_file_from_env() -> Option<std::path::PathBuf> {
+    std::env::var("TICKER_HEALTH_FILE")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .map(Into::into)
+}
+
 fn parse_bool_env(key: &str) -> Option<bool> {
     let v = std::env::var(key).ok()?;
     let v = v.trim();
     if v.is_empty() {
         return None;
     }
-    Some(matches!(v, "1" | "true" | "TRUE" | "yes" | "YES" | "on" | "ON"))
+    Some(matches!(
+        v,
+        "1" | "true" | "TRUE" | "yes" | "YES" | "on" | "ON"
+    ))
 }
 
 fn normalize_os(runtime: &str) -> String {
@@ -1,3 +1,4 @@
+use crate::kite::error::KiteError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -17,6 +18,26 @@ pub enum AppError {
     #[error(transparent)]
     Db(#[from] tokio_postgres::Error),
 
+    #[error(transparent)]
+    Kite(#[from] KiteError),
+
     #[error("Kite API error: {0}")]
     KiteApi(String),
 }
+
+impl AppError {
+    /// The typed Kite error, if this error came from a Kite API response.
+    pub fn kite_error(&self) -> Option<&KiteError> {
+        match self {
+            AppError::Kite(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// True when Kite rejected the access token (expired/invalid session).
+    pub fn is_kite_token_error(&self) -> bool {
+        self.kite_error()
+            .map(|e| e.is_token_error())
+            .unwrap_or(false)
+    }
+}
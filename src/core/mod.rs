@@ -2,6 +2,6 @@ pub mod config;
 pub mod error;
 pub mod state;
 
-pub use config::AppConfig;
+pub use config::{AppConfig, KiteEndpoints};
 pub use error::AppError;
 pub use state::AppState;
@@ -1,5 +1,6 @@
 use super::config::AppConfig;
 use crate::db::Db;
+use crate::kite::ws_pool::KiteTickerPool;
 use crate::ticks::TickStore;
 use std::sync::Arc;
 
@@ -8,4 +9,6 @@ pub struct AppState {
     pub config: Arc<AppConfig>,
     pub db: Arc<Db>,
     pub ticks: Arc<TickStore>,
+    /// Running ticker in this process, if any (reported by `/api/health`).
+    pub ticker: Option<Arc<KiteTickerPool>>,
 }
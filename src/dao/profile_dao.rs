@@ -112,6 +112,22 @@ pub async fn update_session_tokens_for_os(
     Ok(n)
 }
 
+/// Forget the Kite session for a user (after logout / token revocation).
+pub async fn clear_session_tokens_for_os(
+    db: &Db,
+    user_id: &str,
+    os_type: &str,
+) -> Result<u64, AppError> {
+    let n = db
+        .client()
+        .execute(
+            "UPDATE trade.profile SET access_token = NULL, public_token = NULL, updated_at = NOW() WHERE userid = $1 AND os_type = $2",
+            &[&user_id, &os_type],
+        )
+        .await?;
+    Ok(n)
+}
+
 pub async fn get_user_zerodha_login(
     db: &Db,
     user_id: &str,
@@ -1,4 +1,8 @@
 pub mod auth;
 pub mod client;
+pub mod error;
+pub mod ratelimit;
+pub mod retry;
 pub mod types;
 pub mod ws;
+pub mod ws_pool;
@@ -1,11 +1,17 @@
 use crate::core::AppError;
-use crate::kite::types::{KiteEnvelope, SessionToken};
+use crate::kite::client::KiteClient;
+use crate::kite::error::decode_envelope;
+use crate::kite::types::SessionToken;
+use crate::kite::ws::TokenRefresher;
 use reqwest::Url;
 use sha2::{Digest, Sha256};
+use tracing::{info, warn};
 
-const KITE_BASE_URL: &str = "https://api.kite.trade";
-
-pub fn login_url(api_key: &str, callback_url: &str) -> String {
+/// Kite Connect login URL for `api_key`.
+///
+/// `login_base_url` is `KiteEndpoints::login_url` (production:
+/// `https://kite.zerodha.com/connect/login`).
+pub fn login_url(login_base_url: &str, api_key: &str, callback_url: &str) -> String {
     // By default we do NOT pass redirect_url here.
     // Kite already knows the Redirect URL configured for the API key, and in
     // some cases providing `redirect_url=` causes a 400:
@@ -19,7 +25,7 @@ pub fn login_url(api_key: &str, callback_url: &str) -> String {
 
     if !include_redirect {
         return format!(
-            "https://kite.zerodha.com/connect/login?api_key={}&v=3",
+            "{login_base_url}?api_key={}&v=3",
             urlencoding::encode(api_key)
         );
     }
@@ -27,7 +33,7 @@ pub fn login_url(api_key: &str, callback_url: &str) -> String {
     // Minimal escaping for a URL embedded as a query param value.
     let redirect_url = encode_redirect_url_param(callback_url);
     format!(
-        "https://kite.zerodha.com/connect/login?api_key={}&v=3&redirect_url={}",
+        "{login_base_url}?api_key={}&v=3&redirect_url={}",
         urlencoding::encode(api_key),
         redirect_url
     )
@@ -77,12 +83,13 @@ pub fn callback_url_for_user(base: &str, user_id: &str) -> String {
 }
 
 pub async fn exchange_request_token(
+    api_base_url: &str,
     api_key: &str,
     api_secret: &str,
     request_token: &str,
 ) -> Result<SessionToken, AppError> {
     let checksum = checksum(api_key, request_token, api_secret);
-    let url = format!("{KITE_BASE_URL}/session/token");
+    let url = format!("{api_base_url}/session/token");
 
     let resp = reqwest::Client::new()
         .post(url)
@@ -96,20 +103,70 @@ pub async fn exchange_request_token(
 
     let status = resp.status();
     let text = resp.text().await?;
-    if !status.is_success() {
-        return Err(AppError::KiteApi(format!("HTTP {status}: {text}")));
-    }
+    decode_envelope(status, &text)
+}
+
+/// Invalidate an access token (logout). Kite returns `true` on success.
+///
+/// After this call the token is rejected by both REST and the ticker websocket.
+pub async fn invalidate_access_token(
+    api_base_url: &str,
+    api_key: &str,
+    access_token: &str,
+) -> Result<bool, AppError> {
+    let url = format!("{api_base_url}/session/token");
 
-    let envelope: KiteEnvelope<SessionToken> = serde_json::from_str(&text)?;
-    match envelope.status.as_str() {
-        "success" => envelope
-            .data
-            .ok_or_else(|| AppError::KiteApi("Missing data in response".to_string())),
-        _ => Err(AppError::KiteApi(
-            envelope
-                .message
-                .unwrap_or_else(|| "Unknown Kite error".to_string()),
-        )),
+    let resp = reqwest::Client::new()
+        .delete(url)
+        .header("X-Kite-Version", "3")
+        .query(&[("api_key", api_key), ("access_token", access_token)])
+        .send()
+        .await?;
+
+    let status = resp.status();
+    let text = resp.text().await?;
+    decode_envelope(status, &text)
+}
+
+/// Fresh tokens `verify_session` tries before giving up.
+const VERIFY_SESSION_REFRESHES: usize = 3;
+
+/// Check `access_token` with `GET /user/profile` before the ticker connects.
+///
+/// When Kite rejects the token (`TokenException`), `refresher` is asked for a
+/// new one and the check repeats. Returns a client for the token that passed.
+/// Fails with the typed token error when no fresh token is available; other
+/// failures are only logged, since the ticker retries on its own.
+pub async fn verify_session(
+    api_base_url: &str,
+    api_key: &str,
+    access_token: String,
+    refresher: Option<&TokenRefresher>,
+) -> Result<(KiteClient, String), AppError> {
+    let mut access_token = access_token;
+    let mut refreshes = 0;
+    loop {
+        let kite = KiteClient::with_base_url(api_key, &access_token, api_base_url)?;
+        let e = match kite.profile().await {
+            Ok(_) => {
+                info!("kite REST auth preflight OK");
+                return Ok((kite, access_token));
+            }
+            Err(e) if e.is_kite_token_error() => e,
+            Err(e) => {
+                warn!(error = %e, "kite REST auth preflight failed; continuing");
+                return Ok((kite, access_token));
+            }
+        };
+        warn!(error = %e, "kite REST auth preflight: access token rejected");
+        let Some(refresher) = refresher.filter(|_| refreshes < VERIFY_SESSION_REFRESHES) else {
+            return Err(e);
+        };
+        refreshes += 1;
+        match refresher(access_token.clone()).await? {
+            Some(fresh) if fresh != access_token => access_token = fresh,
+            _ => return Err(e),
+        }
     }
 }
 
@@ -1,17 +1,58 @@
-use crate::core::AppError;
-use crate::kite::types::{Holding, KiteEnvelope, UserProfile};
+use crate::core::{AppError, KiteEndpoints};
+use crate::kite::error::{decode_envelope, error_from_response, KiteError};
+use crate::kite::ratelimit::{EndpointClass, RateLimiter};
+use crate::kite::retry::{is_retryable_error, is_retryable_status, RetryPolicy};
+use crate::kite::types::{
+    BasketMargins, Candle, ConvertPositionParams, GttParams, GttResponse, GttTrigger, Holding,
+    Interval, MarginOrderParams, MarginSegment, Margins, ModifyOrderParams, Order, OrderMargin,
+    OrderResponse, PlaceOrderParams, Positions, RawCandles, RawLtpQuote, RawOhlcQuote, RawQuote,
+    SegmentMargins, Trade, UserProfile, Variety,
+};
+use crate::ticks::{now_unix_ns, Tick};
+use chrono::NaiveDateTime;
 use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::Method;
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
 
-const KITE_BASE_URL: &str = "https://api.kite.trade";
+// Per-request instrument limits of the quote endpoints.
+const QUOTE_MAX_INSTRUMENTS: usize = 500;
+const OHLC_MAX_INSTRUMENTS: usize = 1000;
+const LTP_MAX_INSTRUMENTS: usize = 1000;
 
+/// Kite REST client.
+///
+/// Requests are throttled per endpoint class (quote/historical/order/other)
+/// to stay within Kite's rate limits; all clients for the same api_key share
+/// one limiter ([`RateLimiter::for_api_key`]).
+/// Transient failures of GET requests are retried per `RetryPolicy`.
 #[derive(Clone)]
 pub struct KiteClient {
     http: reqwest::Client,
+    base_url: Arc<str>,
+    limiter: Arc<RateLimiter>,
+    retry: RetryPolicy,
 }
 
 impl KiteClient {
+    /// Client for the REST host from `KITE_API_BASE_URL` (default production).
     pub fn new(api_key: &str, access_token: &str) -> Result<Self, AppError> {
+        Self::with_base_url(
+            api_key,
+            access_token,
+            &KiteEndpoints::from_env().api_base_url,
+        )
+    }
+
+    /// Client for an explicit REST host (e.g. `AppConfig::kite.api_base_url`).
+    pub fn with_base_url(
+        api_key: &str,
+        access_token: &str,
+        base_url: &str,
+    ) -> Result<Self, AppError> {
         let mut headers = HeaderMap::new();
         let auth = format!("token {api_key}:{access_token}");
         headers.insert(
@@ -19,14 +60,22 @@ impl KiteClient {
             HeaderValue::from_str(&auth).map_err(|e| AppError::KiteApi(e.to_string()))?,
         );
         // Kite expects this in many endpoints.
-        headers.insert(
-            "X-Kite-Version",
-            HeaderValue::from_static("3"),
-        );
+        headers.insert("X-Kite-Version", HeaderValue::from_static("3"));
         let http = reqwest::Client::builder()
             .default_headers(headers)
             .build()?;
-        Ok(Self { http })
+        Ok(Self {
+            http,
+            base_url: Arc::from(base_url.trim_end_matches('/')),
+            limiter: RateLimiter::for_api_key(api_key),
+            retry: RetryPolicy::from_env(),
+        })
+    }
+
+    /// Override the retry policy (defaults to `RetryPolicy::from_env()`).
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
 
     pub async fn profile(&self) -> Result<UserProfile, AppError> {
@@ -37,40 +86,592 @@ impl KiteClient {
         self.get("/portfolio/holdings").await
     }
 
+    /// Funds and margin usage for one segment.
+    pub async fn margins(&self, segment: MarginSegment) -> Result<SegmentMargins, AppError> {
+        let path = format!("/user/margins/{}", segment.as_str());
+        self.get(&path).await
+    }
+
+    /// Funds and margin usage for all segments.
+    pub async fn all_margins(&self) -> Result<Margins, AppError> {
+        self.get("/user/margins").await
+    }
+
+    /// Margin required by each order, computed independently.
+    pub async fn order_margins(
+        &self,
+        orders: &[MarginOrderParams],
+    ) -> Result<Vec<OrderMargin>, AppError> {
+        self.post_json("/margins/orders", &[], orders).await
+    }
+
+    /// Margin required by a basket of orders placed together (e.g. an option spread).
+    ///
+    /// With `consider_positions`, Kite also nets the basket against open positions.
+    pub async fn basket_margins(
+        &self,
+        orders: &[MarginOrderParams],
+        consider_positions: bool,
+    ) -> Result<BasketMargins, AppError> {
+        let query = [(
+            "consider_positions",
+            if consider_positions { "true" } else { "false" },
+        )];
+        self.post_json("/margins/basket", &query, orders).await
+    }
+
+    /// Full market quotes (OHLC, volume, OI, 5-level depth) as `Tick`s.
+    ///
+    /// `instruments` are `exchange:tradingsymbol` (e.g. `NSE:INFY`) or instrument
+    /// tokens as strings (e.g. `256265`); the result is keyed the same way.
+    /// Large lists are split into batches of 500 and merged.
+    pub async fn quote<S: AsRef<str>>(
+        &self,
+        instruments: &[S],
+    ) -> Result<HashMap<String, Tick>, AppError> {
+        let raw: HashMap<String, RawQuote> = self
+            .get_batched("/quote", instruments, QUOTE_MAX_INSTRUMENTS)
+            .await?;
+        let received_ns = now_unix_ns();
+        Ok(raw
+            .into_iter()
+            .map(|(k, q)| (k, q.into_tick(received_ns)))
+            .collect())
+    }
+
+    /// Last price + day OHLC as `Tick`s (batches of 1000).
+    pub async fn ohlc<S: AsRef<str>>(
+        &self,
+        instruments: &[S],
+    ) -> Result<HashMap<String, Tick>, AppError> {
+        let raw: HashMap<String, RawOhlcQuote> = self
+            .get_batched("/quote/ohlc", instruments, OHLC_MAX_INSTRUMENTS)
+            .await?;
+        let received_ns = now_unix_ns();
+        Ok(raw
+            .into_iter()
+            .map(|(k, q)| (k, q.into_tick(received_ns)))
+            .collect())
+    }
+
+    /// Last traded price as `Tick`s (batches of 1000).
+    pub async fn ltp<S: AsRef<str>>(
+        &self,
+        instruments: &[S],
+    ) -> Result<HashMap<String, Tick>, AppError> {
+        let raw: HashMap<String, RawLtpQuote> = self
+            .get_batched("/quote/ltp", instruments, LTP_MAX_INSTRUMENTS)
+            .await?;
+        let received_ns = now_unix_ns();
+        Ok(raw
+            .into_iter()
+            .map(|(k, q)| (k, q.into_tick(received_ns)))
+            .collect())
+    }
+
+    /// Historical candles for `instrument_token` between `from` and `to`
+    /// (inclusive, exchange local time).
+    ///
+    /// Ranges longer than the interval's per-request limit are fetched in
+    /// consecutive chunks and stitched back together in time order. `from`
+    /// after `to` is a `KiteError::Input`.
+    /// - `continuous`: stitch expired futures contracts (day candles only)
+    /// - `oi`: include open interest in each candle
+    pub async fn historical(
+        &self,
+        instrument_token: i32,
+        interval: Interval,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        continuous: bool,
+        oi: bool,
+    ) -> Result<Vec<Candle>, AppError> {
+        if from > to {
+            // Checked locally, so there is no HTTP status.
+            return Err(KiteError::Input {
+                status: 0,
+                message: format!("historical range is empty: from {from} is after to {to}"),
+            }
+            .into());
+        }
+        let path = format!(
+            "/instruments/historical/{instrument_token}/{}",
+            interval.as_str()
+        );
+        let mut out: Vec<Candle> = Vec::new();
+        for (chunk_from, chunk_to) in historical_chunks(interval, from, to) {
+            let chunk_from = chunk_from.format("%Y-%m-%d %H:%M:%S").to_string();
+            let chunk_to = chunk_to.format("%Y-%m-%d %H:%M:%S").to_string();
+            let query = [
+                ("from", chunk_from.as_str()),
+                ("to", chunk_to.as_str()),
+                ("continuous", if continuous { "1" } else { "0" }),
+                ("oi", if oi { "1" } else { "0" }),
+            ];
+            let url = self.url(&path);
+            let raw: RawCandles = self
+                .send(EndpointClass::Historical, self.http.get(url).query(&query))
+                .await?;
+            let candles = raw.into_candles().map_err(AppError::KiteApi)?;
+
+            // Chunks do not overlap, but guard against boundary duplicates anyway.
+            let last_ts = out.last().map(|c| c.timestamp);
+            out.extend(
+                candles
+                    .into_iter()
+                    .filter(|c| last_ts.map_or(true, |ts| c.timestamp > ts)),
+            );
+        }
+        Ok(out)
+    }
+
+    /// Net and day positions.
+    pub async fn positions(&self) -> Result<Positions, AppError> {
+        self.get("/portfolio/positions").await
+    }
+
+    /// Convert an open position between products (MIS <-> NRML <-> CNC).
+    pub async fn convert_position(&self, params: &ConvertPositionParams) -> Result<bool, AppError> {
+        self.put_form("/portfolio/positions", params).await
+    }
+
+    /// Place an order. Returns the Kite `order_id`.
+    pub async fn place_order(
+        &self,
+        variety: Variety,
+        params: &PlaceOrderParams,
+    ) -> Result<OrderResponse, AppError> {
+        let path = format!("/orders/{}", variety.as_str());
+        self.post_form(&path, params).await
+    }
+
+    /// Modify a pending order. Only the `Some` fields in `params` are changed.
+    pub async fn modify_order(
+        &self,
+        variety: Variety,
+        order_id: &str,
+        params: &ModifyOrderParams,
+    ) -> Result<OrderResponse, AppError> {
+        let path = format!(
+            "/orders/{}/{}",
+            variety.as_str(),
+            urlencoding::encode(order_id)
+        );
+        self.put_form(&path, params).await
+    }
+
+    /// Cancel a pending order.
+    ///
+    /// For `co` second legs, pass the first leg's id as `parent_order_id`.
+    pub async fn cancel_order(
+        &self,
+        variety: Variety,
+        order_id: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<OrderResponse, AppError> {
+        let path = format!(
+            "/orders/{}/{}",
+            variety.as_str(),
+            urlencoding::encode(order_id)
+        );
+        let query: Vec<(&str, &str)> = parent_order_id
+            .map(|p| vec![("parent_order_id", p)])
+            .unwrap_or_default();
+        self.delete(&path, &query).await
+    }
+
+    /// Today's order book (every order placed during the day, latest state).
+    pub async fn orders(&self) -> Result<Vec<Order>, AppError> {
+        self.get("/orders").await
+    }
+
+    /// Every state transition of one order, oldest first.
+    pub async fn order_history(&self, order_id: &str) -> Result<Vec<Order>, AppError> {
+        let path = format!("/orders/{}", urlencoding::encode(order_id));
+        self.get(&path).await
+    }
+
+    /// Today's trade book (all fills across orders).
+    pub async fn trades(&self) -> Result<Vec<Trade>, AppError> {
+        self.get("/trades").await
+    }
+
+    /// Fills generated by one order.
+    pub async fn order_trades(&self, order_id: &str) -> Result<Vec<Trade>, AppError> {
+        let path = format!("/orders/{}/trades", urlencoding::encode(order_id));
+        self.get(&path).await
+    }
+
+    /// Create a GTT trigger.
+    pub async fn create_gtt(&self, params: &GttParams) -> Result<GttResponse, AppError> {
+        let form = gtt_form(params)?;
+        self.post_form("/gtt/triggers", &form).await
+    }
+
+    /// All GTT triggers (active and recently finished).
+    pub async fn gtts(&self) -> Result<Vec<GttTrigger>, AppError> {
+        self.get("/gtt/triggers").await
+    }
+
+    pub async fn gtt(&self, trigger_id: i64) -> Result<GttTrigger, AppError> {
+        self.get(&format!("/gtt/triggers/{trigger_id}")).await
+    }
+
+    /// Replace an active trigger's condition and orders.
+    pub async fn modify_gtt(
+        &self,
+        trigger_id: i64,
+        params: &GttParams,
+    ) -> Result<GttResponse, AppError> {
+        let form = gtt_form(params)?;
+        self.put_form(&format!("/gtt/triggers/{trigger_id}"), &form)
+            .await
+    }
+
+    pub async fn delete_gtt(&self, trigger_id: i64) -> Result<GttResponse, AppError> {
+        let query: [(&str, &str); 0] = [];
+        self.delete(&format!("/gtt/triggers/{trigger_id}"), &query)
+            .await
+    }
+
     /// Download the full instruments dump as CSV.
     ///
     /// Note: this is NOT a JSON envelope endpoint.
     pub async fn instruments_csv(&self) -> Result<String, AppError> {
-        let url = format!("{KITE_BASE_URL}/instruments");
-        let resp = self.http.get(url).send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
+        let url = self.url("/instruments");
+        let (status, text) = self
+            .execute(EndpointClass::Default, self.http.get(url))
+            .await?;
         if !status.is_success() {
-            return Err(AppError::KiteApi(format!("HTTP {status}: {text}")));
+            return Err(error_from_response(status, &text).into());
         }
         Ok(text)
     }
 
     async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, AppError> {
-        let url = format!("{KITE_BASE_URL}{path}");
-        let resp = self.http.get(url).send().await?;
-        let status = resp.status();
-        let text = resp.text().await?;
+        let url = self.url(path);
+        self.send(EndpointClass::of(&Method::GET, path), self.http.get(url))
+            .await
+    }
 
-        if !status.is_success() {
-            return Err(AppError::KiteApi(format!("HTTP {status}: {text}")));
+    /// GET a map-returning endpoint with `i=` params, split at `max_per_call`.
+    async fn get_batched<V: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        path: &str,
+        instruments: &[S],
+        max_per_call: usize,
+    ) -> Result<HashMap<String, V>, AppError> {
+        let mut out = HashMap::with_capacity(instruments.len());
+        for chunk in instruments.chunks(max_per_call) {
+            let query: Vec<(&str, &str)> = chunk.iter().map(|i| ("i", i.as_ref())).collect();
+            let url = self.url(path);
+            let part: HashMap<String, V> = self
+                .send(
+                    EndpointClass::of(&Method::GET, path),
+                    self.http.get(url).query(&query),
+                )
+                .await?;
+            out.extend(part);
         }
+        Ok(out)
+    }
+
+    async fn post_form<T: DeserializeOwned, F: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        form: &F,
+    ) -> Result<T, AppError> {
+        let url = self.url(path);
+        self.send(
+            EndpointClass::of(&Method::POST, path),
+            self.http.post(url).form(form),
+        )
+        .await
+    }
+
+    async fn post_json<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &B,
+    ) -> Result<T, AppError> {
+        let url = self.url(path);
+        self.send(
+            EndpointClass::of(&Method::POST, path),
+            self.http.post(url).query(query).json(body),
+        )
+        .await
+    }
+
+    async fn put_form<T: DeserializeOwned, F: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        form: &F,
+    ) -> Result<T, AppError> {
+        let url = self.url(path);
+        self.send(
+            EndpointClass::of(&Method::PUT, path),
+            self.http.put(url).form(form),
+        )
+        .await
+    }
+
+    async fn delete<T: DeserializeOwned, Q: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        query: &Q,
+    ) -> Result<T, AppError> {
+        let url = self.url(path);
+        self.send(
+            EndpointClass::of(&Method::DELETE, path),
+            self.http.delete(url).query(query),
+        )
+        .await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Send a request (after rate limiting) and unwrap Kite's `{status, data, message}` envelope.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        class: EndpointClass,
+        req: reqwest::RequestBuilder,
+    ) -> Result<T, AppError> {
+        let (status, text) = self.execute(class, req).await?;
+        decode_envelope(status, &text)
+    }
+
+    /// Send a request and read the body, retrying transient failures.
+    ///
+    /// Only GETs are retried: order endpoints are not idempotent.
+    async fn execute(
+        &self,
+        class: EndpointClass,
+        mut req: reqwest::RequestBuilder,
+    ) -> Result<(reqwest::StatusCode, String), AppError> {
+        let max_retries = req
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| self.retry.max_retries_for(r.method()))
+            .unwrap_or(0);
+
+        let mut attempt: u32 = 0;
+        loop {
+            // Keep a copy for the next attempt before `send` consumes the builder.
+            let retry_req = if attempt < max_retries {
+                req.try_clone()
+            } else {
+                None
+            };
+
+            self.limiter.acquire(class).await;
+            let outcome = match req.send().await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let headers = resp.headers().clone();
+                    resp.text().await.map(|text| (status, headers, text))
+                }
+                Err(e) => Err(e),
+            };
+
+            let delay = match (&outcome, &retry_req) {
+                (Ok((status, headers, _)), Some(_)) if is_retryable_status(*status) => Some((
+                    self.retry.delay_for(attempt + 1, headers),
+                    format!("HTTP {status}"),
+                )),
+                (Err(e), Some(_)) if is_retryable_error(e) => {
+                    Some((self.retry.backoff(attempt + 1), e.to_string()))
+                }
+                _ => None,
+            };
+
+            match (delay, retry_req) {
+                (Some((delay, reason)), Some(next)) => {
+                    attempt += 1;
+                    warn!(
+                        endpoint_class = class.as_str(),
+                        attempt = attempt,
+                        max_retries = max_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        reason = %reason,
+                        "kite request failed; retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    req = next;
+                }
+                _ => {
+                    let (status, _, text) = outcome?;
+                    return Ok((status, text));
+                }
+            }
+        }
+    }
+}
+
+/// GTT create/modify take `condition` and `orders` as JSON strings inside a form body.
+fn gtt_form(params: &GttParams) -> Result<[(&'static str, String); 3], AppError> {
+    Ok([
+        ("type", params.kind.as_str().to_string()),
+        ("condition", serde_json::to_string(&params.condition)?),
+        ("orders", serde_json::to_string(&params.orders)?),
+    ])
+}
+
+/// Split `from..=to` into consecutive, non-overlapping windows no longer than
+/// the interval's per-request limit.
+fn historical_chunks(
+    interval: Interval,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let max_span = chrono::Duration::days(interval.max_days_per_request());
+    let mut out = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = (start + max_span).min(to);
+        out.push((start, end));
+        start = end + chrono::Duration::seconds(1);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kite::retry::Jitter;
+    use crate::kite::types::Variety;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::Duration;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    fn secs(s: i64) -> chrono::Duration {
+        chrono::Duration::seconds(s)
+    }
+
+    #[test]
+    fn historical_range_shorter_than_a_chunk_is_one_request() {
+        let (from, to) = (at("2024-06-10 09:15:00"), at("2024-06-10 15:30:00"));
+        assert_eq!(
+            historical_chunks(Interval::Minute, from, to),
+            vec![(from, to)]
+        );
+        // A single instant is still one (degenerate) window.
+        assert_eq!(
+            historical_chunks(Interval::Day, from, from),
+            vec![(from, from)]
+        );
+    }
+
+    #[test]
+    fn historical_chunks_split_exactly_at_the_limit() {
+        let from = at("2024-01-01 00:00:00");
+        let limit = chrono::Duration::days(Interval::Minute.max_days_per_request());
+
+        // Exactly the limit: one window.
+        let to = from + limit;
+        assert_eq!(
+            historical_chunks(Interval::Minute, from, to),
+            vec![(from, to)]
+        );
+
+        // One second over: a second, one-instant window.
+        let to = from + limit + secs(1);
+        assert_eq!(
+            historical_chunks(Interval::Minute, from, to),
+            vec![(from, from + limit), (to, to)]
+        );
 
-        let envelope: KiteEnvelope<T> = serde_json::from_str(&text)?;
-        match envelope.status.as_str() {
-            "success" => envelope
-                .data
-                .ok_or_else(|| AppError::KiteApi("Missing data in response".to_string())),
-            _ => Err(AppError::KiteApi(
-                envelope
-                    .message
-                    .unwrap_or_else(|| "Unknown Kite error".to_string()),
-            )),
+        // Two full windows end to end, each starting a second after the last.
+        let to = from + limit + secs(1) + limit;
+        let chunks = historical_chunks(Interval::Minute, from, to);
+        assert_eq!(
+            chunks,
+            vec![(from, from + limit), (from + limit + secs(1), to)]
+        );
+    }
+
+    #[test]
+    fn historical_chunks_cover_long_ranges_without_gaps_or_overlap() {
+        let (from, to) = (at("2015-01-01 00:00:00"), at("2024-06-10 23:59:59"));
+        for interval in [
+            Interval::Minute,
+            Interval::Minute15,
+            Interval::Minute60,
+            Interval::Day,
+        ] {
+            let limit = chrono::Duration::days(interval.max_days_per_request());
+            let chunks = historical_chunks(interval, from, to);
+            assert_eq!(chunks.first().unwrap().0, from);
+            assert_eq!(chunks.last().unwrap().1, to);
+            for (start, end) in &chunks {
+                assert!(start <= end && *end - *start <= limit);
+            }
+            for pair in chunks.windows(2) {
+                assert_eq!(pair[1].0, pair[0].1 + secs(1));
+            }
         }
     }
+
+    #[tokio::test]
+    async fn historical_rejects_from_after_to() {
+        let (from, to) = (at("2024-06-10 15:30:00"), at("2024-06-10 09:15:00"));
+        assert!(historical_chunks(Interval::Minute, from, to).is_empty());
+
+        // Fails before any request is sent, so the host is never contacted.
+        let client =
+            KiteClient::with_base_url("chunks-test", "token", "http://127.0.0.1:9").unwrap();
+        let err = client
+            .historical(256265, Interval::Minute, from, to, false, false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err.kite_error(), Some(KiteError::Input { status: 0, .. })),
+            "{err}"
+        );
+        assert!(err.to_string().contains("is after"), "{err}");
+    }
+
+    /// Local server answering every request with 503; returns its base URL and hit counter.
+    async fn unavailable_server() -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().fallback(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { (axum::http::StatusCode::SERVICE_UNAVAILABLE, "down") }
+        });
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        (format!("http://{addr}"), hits)
+    }
+
+    #[tokio::test]
+    async fn retries_gets_but_never_order_calls() {
+        let (base_url, hits) = unavailable_server().await;
+        let retry = RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            jitter: Jitter::new(|_| 0),
+        };
+        let client = KiteClient::with_base_url("retry-test", "token", &base_url)
+            .unwrap()
+            .with_retry_policy(retry);
+
+        assert!(client.profile().await.is_err());
+        assert_eq!(hits.swap(0, Ordering::SeqCst), 3);
+
+        let cancelled = client.cancel_order(Variety::Regular, "1", None).await;
+        assert!(cancelled.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
 }
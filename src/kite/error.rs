@@ -0,0 +1,126 @@
+use crate::core::AppError;
+use crate::kite::types::KiteEnvelope;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Error reported by the Kite API, classified by its `error_type`.
+///
+/// `status` is the HTTP status of the response (Kite also uses 200 with
+/// `"status": "error"` in a few places).
+#[derive(Debug, Clone, Error)]
+pub enum KiteError {
+    /// Session expired or invalid; the user has to log in again.
+    #[error("Kite TokenException (HTTP {status}): {message}")]
+    Token { status: u16, message: String },
+
+    /// The API key is not allowed to call this endpoint.
+    #[error("Kite PermissionException (HTTP {status}): {message}")]
+    Permission { status: u16, message: String },
+
+    /// Missing or invalid request parameters.
+    #[error("Kite InputException (HTTP {status}): {message}")]
+    Input { status: u16, message: String },
+
+    /// Order placement / modification / cancellation failed.
+    #[error("Kite OrderException (HTTP {status}): {message}")]
+    Order { status: u16, message: String },
+
+    /// Kite could not reach the exchange/OMS.
+    #[error("Kite NetworkException (HTTP {status}): {message}")]
+    Network { status: u16, message: String },
+
+    /// Kite's backend returned data it could not parse.
+    #[error("Kite DataException (HTTP {status}): {message}")]
+    Data { status: u16, message: String },
+
+    /// Anything else, including unknown `error_type` values and non-JSON errors.
+    #[error("Kite GeneralException (HTTP {status}): {message}")]
+    General { status: u16, message: String },
+}
+
+impl KiteError {
+    pub fn new(error_type: Option<&str>, status: u16, message: String) -> Self {
+        match error_type.unwrap_or_default() {
+            "TokenException" => KiteError::Token { status, message },
+            "PermissionException" => KiteError::Permission { status, message },
+            "InputException" => KiteError::Input { status, message },
+            "OrderException" => KiteError::Order { status, message },
+            "NetworkException" => KiteError::Network { status, message },
+            "DataException" => KiteError::Data { status, message },
+            _ => KiteError::General { status, message },
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            KiteError::Token { status, .. }
+            | KiteError::Permission { status, .. }
+            | KiteError::Input { status, .. }
+            | KiteError::Order { status, .. }
+            | KiteError::Network { status, .. }
+            | KiteError::Data { status, .. }
+            | KiteError::General { status, .. } => *status,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            KiteError::Token { message, .. }
+            | KiteError::Permission { message, .. }
+            | KiteError::Input { message, .. }
+            | KiteError::Order { message, .. }
+            | KiteError::Network { message, .. }
+            | KiteError::Data { message, .. }
+            | KiteError::General { message, .. } => message,
+        }
+    }
+
+    /// True when the access token is expired/invalid and a re-login is needed.
+    pub fn is_token_error(&self) -> bool {
+        matches!(self, KiteError::Token { .. })
+    }
+}
+
+/// Build a `KiteError` from a non-2xx response body.
+///
+/// Kite error bodies are envelopes carrying `error_type` + `message`; plain-text
+/// bodies (e.g. from a proxy) become `General`.
+pub(crate) fn error_from_response(status: StatusCode, text: &str) -> KiteError {
+    match serde_json::from_str::<KiteEnvelope<serde_json::Value>>(text) {
+        Ok(envelope) => KiteError::new(
+            envelope.error_type.as_deref(),
+            status.as_u16(),
+            envelope.message.unwrap_or_else(|| text.to_string()),
+        ),
+        Err(_) => KiteError::General {
+            status: status.as_u16(),
+            message: text.to_string(),
+        },
+    }
+}
+
+/// Unwrap Kite's `{status, data, message, error_type}` envelope.
+pub(crate) fn decode_envelope<T: DeserializeOwned>(
+    status: StatusCode,
+    text: &str,
+) -> Result<T, AppError> {
+    if !status.is_success() {
+        return Err(error_from_response(status, text).into());
+    }
+
+    let envelope: KiteEnvelope<T> = serde_json::from_str(text)?;
+    match envelope.status.as_str() {
+        "success" => envelope
+            .data
+            .ok_or_else(|| AppError::KiteApi("Missing data in response".to_string())),
+        _ => Err(KiteError::new(
+            envelope.error_type.as_deref(),
+            status.as_u16(),
+            envelope
+                .message
+                .unwrap_or_else(|| "Unknown Kite error".to_string()),
+        )
+        .into()),
+    }
+}
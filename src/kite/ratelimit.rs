@@ -0,0 +1,208 @@
+use dashmap::DashMap;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::info;
+
+/// Kite REST endpoints grouped by the rate limit that applies to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// `/quote`, `/quote/ohlc`, `/quote/ltp`
+    Quote,
+    /// `/instruments/historical/...`
+    Historical,
+    /// Order placement, modification and cancellation.
+    Order,
+    /// Everything else.
+    Default,
+}
+
+impl EndpointClass {
+    pub fn of(method: &Method, path: &str) -> Self {
+        if path.starts_with("/quote") {
+            EndpointClass::Quote
+        } else if path.starts_with("/instruments/historical") {
+            EndpointClass::Historical
+        } else if path.starts_with("/orders") && *method != Method::GET {
+            EndpointClass::Order
+        } else {
+            EndpointClass::Default
+        }
+    }
+
+    /// Requests per second allowed by Kite for this class.
+    pub fn default_rate_per_sec(&self) -> f64 {
+        match self {
+            EndpointClass::Quote => 1.0,
+            EndpointClass::Historical => 3.0,
+            EndpointClass::Order => 10.0,
+            EndpointClass::Default => 10.0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndpointClass::Quote => "quote",
+            EndpointClass::Historical => "historical",
+            EndpointClass::Order => "order",
+            EndpointClass::Default => "default",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn new(rate_per_sec: f64) -> Self {
+        // Burst equals one second's worth of requests.
+        let capacity = rate_per_sec.max(1.0);
+        Self {
+            rate_per_sec,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take one token and return how long the caller must wait before sending.
+    ///
+    /// Tokens may go negative: each waiter reserves its slot up front, so
+    /// concurrent callers queue in order without holding the lock while sleeping.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        self.last_refill = self.last_refill.max(now);
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate_per_sec)
+        }
+    }
+}
+
+/// Token-bucket limiter with one bucket per `EndpointClass`.
+///
+/// Kite's limits apply per API key, so `KiteClient` takes its limiter from
+/// [`RateLimiter::for_api_key`]: every client for the same key, and every
+/// clone of one, shares the same budget.
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: HashMap<EndpointClass, Mutex<Bucket>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        let buckets = [
+            EndpointClass::Quote,
+            EndpointClass::Historical,
+            EndpointClass::Order,
+            EndpointClass::Default,
+        ]
+        .into_iter()
+        .map(|c| (c, Mutex::new(Bucket::new(c.default_rate_per_sec()))))
+        .collect();
+        Self { buckets }
+    }
+}
+
+impl RateLimiter {
+    /// The process-wide limiter for `api_key`, created on first use.
+    pub fn for_api_key(api_key: &str) -> Arc<RateLimiter> {
+        static BY_API_KEY: OnceLock<DashMap<String, Arc<RateLimiter>>> = OnceLock::new();
+        BY_API_KEY
+            .get_or_init(DashMap::new)
+            .entry(api_key.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Wait until a request of `class` may be sent.
+    pub async fn acquire(&self, class: EndpointClass) {
+        let Some(bucket) = self.buckets.get(&class) else {
+            return;
+        };
+        let wait = bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reserve(Instant::now());
+        if !wait.is_zero() {
+            info!(
+                endpoint_class = class.as_str(),
+                delay_ms = wait.as_millis() as u64,
+                "kite rate limit: request delayed"
+            );
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(d: Duration) -> u64 {
+        d.as_millis() as u64
+    }
+
+    #[test]
+    fn bucket_allows_a_one_second_burst_then_spaces_requests() {
+        let start = Instant::now();
+        let mut b = Bucket::new(3.0);
+        for _ in 0..3 {
+            assert_eq!(b.reserve(start), Duration::ZERO);
+        }
+        // Each further caller reserves the next slot: 1/3 s apart.
+        assert_eq!(ms(b.reserve(start)), 333);
+        assert_eq!(ms(b.reserve(start)), 666);
+        assert_eq!(ms(b.reserve(start)), 1000);
+        assert_eq!(b.tokens, -3.0);
+    }
+
+    #[test]
+    fn negative_reservations_are_paid_back_before_new_requests_pass() {
+        let start = Instant::now();
+        let mut b = Bucket::new(1.0);
+        assert_eq!(b.reserve(start), Duration::ZERO);
+        assert_eq!(ms(b.reserve(start)), 1000);
+        assert_eq!(ms(b.reserve(start)), 2000);
+
+        // 1 s later one slot has been repaid; the queue is still two deep.
+        let later = start + Duration::from_secs(1);
+        assert_eq!(ms(b.reserve(later)), 2000);
+        // Once the debt is repaid the bucket refills, but never past its burst.
+        let idle = later + Duration::from_secs(60);
+        assert_eq!(b.reserve(idle), Duration::ZERO);
+        assert_eq!(ms(b.reserve(idle)), 1000);
+    }
+
+    #[test]
+    fn clock_going_backwards_does_not_refill() {
+        let start = Instant::now() + Duration::from_secs(5);
+        let mut b = Bucket::new(1.0);
+        b.last_refill = start;
+        assert_eq!(b.reserve(start), Duration::ZERO);
+        assert_eq!(ms(b.reserve(start - Duration::from_secs(3))), 1000);
+        // The earlier instant must not count as a refill point either.
+        assert_eq!(ms(b.reserve(start)), 2000);
+    }
+
+    #[test]
+    fn limiter_is_shared_per_api_key() {
+        let a = RateLimiter::for_api_key("ratelimit-test-a");
+        let again = RateLimiter::for_api_key("ratelimit-test-a");
+        let other = RateLimiter::for_api_key("ratelimit-test-b");
+        assert!(Arc::ptr_eq(&a, &again));
+        assert!(!Arc::ptr_eq(&a, &other));
+    }
+}
@@ -0,0 +1,217 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Source of backoff jitter: given `max`, returns a value in `0..=max`.
+///
+/// The default draws from std's randomly keyed hasher; tests inject a
+/// deterministic one with [`Jitter::new`].
+#[derive(Clone)]
+pub struct Jitter(Arc<dyn Fn(u64) -> u64 + Send + Sync>);
+
+impl Jitter {
+    pub fn new(f: impl Fn(u64) -> u64 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Uniform random jitter (each `RandomState` gets fresh random keys).
+    pub fn random() -> Self {
+        Self::new(|max| {
+            let v = RandomState::new().build_hasher().finish();
+            max.checked_add(1).map_or(v, |n| v % n)
+        })
+    }
+
+    fn sample(&self, max: u64) -> u64 {
+        (self.0)(max).min(max)
+    }
+}
+
+impl Default for Jitter {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+impl std::fmt::Debug for Jitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Jitter(..)")
+    }
+}
+
+/// Retry policy for idempotent Kite REST calls (GETs only).
+///
+/// Retries 429, 5xx and connection-level failures with jittered exponential
+/// backoff. A `Retry-After` header (in seconds) overrides the computed delay.
+/// Order placement/modification/cancellation is never retried, since a
+/// retried POST can place the same order twice.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying).
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Jitter,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+            jitter: Jitter::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Default: 3 retries, 250ms base delay, 10s max delay.
+    ///
+    /// Env:
+    /// - KITE_HTTP_MAX_RETRIES (default 3; 0 disables)
+    /// - KITE_HTTP_RETRY_BASE_MS (default 250)
+    /// - KITE_HTTP_RETRY_MAX_MS (default 10000)
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        let env_u64 = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        Self {
+            max_retries: env_u64("KITE_HTTP_MAX_RETRIES")
+                .map(|v| v as u32)
+                .unwrap_or(d.max_retries),
+            base_delay: env_u64("KITE_HTTP_RETRY_BASE_MS")
+                .filter(|v| *v > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(d.base_delay),
+            max_delay: env_u64("KITE_HTTP_RETRY_MAX_MS")
+                .filter(|v| *v > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(d.max_delay),
+            jitter: d.jitter,
+        }
+    }
+
+    /// Replace the jitter source (e.g. a fixed one in tests).
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Retries allowed for a request with `method`: only GETs are retried.
+    pub fn max_retries_for(&self, method: &Method) -> u32 {
+        if *method == Method::GET {
+            self.max_retries
+        } else {
+            0
+        }
+    }
+
+    /// Delay before retry number `attempt` (1-based).
+    ///
+    /// "Equal jitter": half of the exponential delay is fixed, the other half random,
+    /// so concurrent clients do not retry in lockstep.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let half = exp / 2;
+        half + Duration::from_nanos(self.jitter.sample(half.as_nanos() as u64))
+    }
+
+    /// Delay for a retryable response: `Retry-After` when present, else backoff.
+    pub fn delay_for(&self, attempt: u32, headers: &HeaderMap) -> Duration {
+        retry_after(headers)
+            .map(|d| d.min(self.max_delay))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+}
+
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Transport failures worth retrying: connect errors, timeouts, resets mid-request.
+pub fn is_retryable_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let v = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    v.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy(jitter: impl Fn(u64) -> u64 + Send + Sync + 'static) -> RetryPolicy {
+        RetryPolicy::default().with_jitter(Jitter::new(jitter))
+    }
+
+    fn ms(d: Duration) -> u64 {
+        d.as_millis() as u64
+    }
+
+    #[test]
+    fn backoff_is_half_fixed_half_jitter_and_capped() {
+        let low = policy(|_| 0);
+        let high = policy(|max| max);
+        // 250ms base doubling per attempt, capped at the 10s max delay.
+        let attempts = [1, 2, 3, 6, 7, 40];
+        let exps = [250, 500, 1000, 8000, 10_000, 10_000];
+        for (attempt, exp) in attempts.into_iter().zip(exps) {
+            assert_eq!(ms(low.backoff(attempt)), exp / 2, "attempt {attempt}");
+            assert_eq!(ms(high.backoff(attempt)), exp, "attempt {attempt}");
+        }
+        // An out-of-range jitter source is clamped.
+        assert_eq!(ms(policy(|_| u64::MAX).backoff(1)), 250);
+    }
+
+    #[test]
+    fn random_backoff_stays_within_bounds() {
+        let p = RetryPolicy::default();
+        let delays: Vec<Duration> = (0..200).map(|_| p.backoff(3)).collect();
+        assert!(delays
+            .iter()
+            .all(|d| *d >= Duration::from_millis(500) && *d <= Duration::from_millis(1000)));
+        // Not a constant: concurrent clients must not retry in lockstep.
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[test]
+    fn retry_after_overrides_backoff_up_to_the_max_delay() {
+        let p = policy(|_| 0);
+        let with = |v: &'static str| {
+            let mut h = HeaderMap::new();
+            h.insert(RETRY_AFTER, HeaderValue::from_static(v));
+            p.delay_for(1, &h)
+        };
+        assert_eq!(with("2"), Duration::from_secs(2));
+        assert_eq!(with("120"), Duration::from_secs(10));
+        // Unparseable (including the HTTP-date form) falls back to backoff.
+        assert_eq!(ms(with("soon")), 125);
+        assert_eq!(ms(with("Wed, 21 Oct 2015 07:28:00 GMT")), 125);
+        assert_eq!(ms(p.delay_for(1, &HeaderMap::new())), 125);
+    }
+
+    #[test]
+    fn only_gets_are_retried() {
+        let p = RetryPolicy::default();
+        assert_eq!(p.max_retries_for(&Method::GET), 3);
+        for m in [Method::POST, Method::PUT, Method::DELETE, Method::PATCH] {
+            assert_eq!(p.max_retries_for(&m), 0, "{m}");
+        }
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::kite::error::KiteError;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
@@ -35,3 +36,1186 @@ pub struct SessionToken {
     pub public_token: Option<String>,
     pub user_id: Option<String>,
 }
+
+/// Order variety; selects the `/orders/{variety}` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variety {
+    Regular,
+    Amo,
+    Co,
+    Iceberg,
+    /// Only seen in the order book; auction orders are not placed from here.
+    Auction,
+}
+
+impl Variety {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Variety::Regular => "regular",
+            Variety::Amo => "amo",
+            Variety::Co => "co",
+            Variety::Iceberg => "iceberg",
+            Variety::Auction => "auction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TransactionType {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OrderType {
+    #[serde(rename = "MARKET")]
+    Market,
+    #[serde(rename = "LIMIT")]
+    Limit,
+    #[serde(rename = "SL")]
+    Sl,
+    #[serde(rename = "SL-M")]
+    SlM,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Product {
+    Cnc,
+    Nrml,
+    Mis,
+    Mtf,
+    /// Reported for cover-order legs in the order book.
+    Co,
+    /// Legacy bracket-order product; only seen in historical orders.
+    Bo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Validity {
+    Day,
+    Ioc,
+    Ttl,
+}
+
+/// Form body for `POST /orders/{variety}`.
+///
+/// Variety-specific fields:
+/// - `co`: `trigger_price` is the stoploss leg and is required
+/// - `iceberg`: `iceberg_legs` (2-10) and `iceberg_quantity` are required
+/// - `validity=TTL`: `validity_ttl` (minutes) is required
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaceOrderParams {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub transaction_type: TransactionType,
+    pub order_type: OrderType,
+    pub quantity: u32,
+    pub product: Product,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disclosed_quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<Validity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity_ttl: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iceberg_legs: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iceberg_quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl PlaceOrderParams {
+    pub fn new(
+        exchange: impl Into<String>,
+        tradingsymbol: impl Into<String>,
+        transaction_type: TransactionType,
+        order_type: OrderType,
+        quantity: u32,
+        product: Product,
+    ) -> Self {
+        Self {
+            exchange: exchange.into(),
+            tradingsymbol: tradingsymbol.into(),
+            transaction_type,
+            order_type,
+            quantity,
+            product,
+            price: None,
+            trigger_price: None,
+            disclosed_quantity: None,
+            validity: None,
+            validity_ttl: None,
+            iceberg_legs: None,
+            iceberg_quantity: None,
+            tag: None,
+        }
+    }
+}
+
+/// Form body for `PUT /orders/{variety}/{order_id}`.
+///
+/// Only the fields that are `Some` are sent; Kite keeps the rest unchanged.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModifyOrderParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<OrderType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<Validity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disclosed_quantity: Option<u32>,
+}
+
+/// Response of place/modify/cancel order calls.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OrderResponse {
+    pub order_id: String,
+}
+
+/// Order status as reported by Kite.
+///
+/// Kite also emits transient statuses while an order moves through the RMS
+/// and the exchange (e.g. `VALIDATION PENDING`); those that we do not model
+/// explicitly are kept verbatim in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum OrderStatus {
+    Open,
+    Complete,
+    Cancelled,
+    Rejected,
+    TriggerPending,
+    PutOrderReqReceived,
+    ValidationPending,
+    OpenPending,
+    ModifyValidationPending,
+    ModifyPending,
+    CancelPending,
+    AmoReqReceived,
+    Other(String),
+}
+
+impl OrderStatus {
+    /// True once the order can no longer change (filled, cancelled or rejected).
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Complete | OrderStatus::Cancelled | OrderStatus::Rejected
+        )
+    }
+}
+
+impl From<String> for OrderStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "OPEN" => OrderStatus::Open,
+            "COMPLETE" => OrderStatus::Complete,
+            "CANCELLED" => OrderStatus::Cancelled,
+            "REJECTED" => OrderStatus::Rejected,
+            "TRIGGER PENDING" => OrderStatus::TriggerPending,
+            "PUT ORDER REQ RECEIVED" => OrderStatus::PutOrderReqReceived,
+            "VALIDATION PENDING" => OrderStatus::ValidationPending,
+            "OPEN PENDING" => OrderStatus::OpenPending,
+            "MODIFY VALIDATION PENDING" => OrderStatus::ModifyValidationPending,
+            "MODIFY PENDING" => OrderStatus::ModifyPending,
+            "CANCEL PENDING" => OrderStatus::CancelPending,
+            "AMO REQ RECEIVED" => OrderStatus::AmoReqReceived,
+            _ => OrderStatus::Other(s),
+        }
+    }
+}
+
+impl From<OrderStatus> for String {
+    fn from(s: OrderStatus) -> Self {
+        match s {
+            OrderStatus::Open => "OPEN".to_string(),
+            OrderStatus::Complete => "COMPLETE".to_string(),
+            OrderStatus::Cancelled => "CANCELLED".to_string(),
+            OrderStatus::Rejected => "REJECTED".to_string(),
+            OrderStatus::TriggerPending => "TRIGGER PENDING".to_string(),
+            OrderStatus::PutOrderReqReceived => "PUT ORDER REQ RECEIVED".to_string(),
+            OrderStatus::ValidationPending => "VALIDATION PENDING".to_string(),
+            OrderStatus::OpenPending => "OPEN PENDING".to_string(),
+            OrderStatus::ModifyValidationPending => "MODIFY VALIDATION PENDING".to_string(),
+            OrderStatus::ModifyPending => "MODIFY PENDING".to_string(),
+            OrderStatus::CancelPending => "CANCEL PENDING".to_string(),
+            OrderStatus::AmoReqReceived => "AMO REQ RECEIVED".to_string(),
+            OrderStatus::Other(s) => s,
+        }
+    }
+}
+
+/// Kite timestamps are `yyyy-mm-dd hh:mm:ss` in exchange local time (IST),
+/// and may be `null` or `""` for orders that never reached the exchange.
+pub(crate) mod kite_time {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(t) => serializer.serialize_str(&t.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        let s: Option<String> = Option::deserialize(deserializer)?;
+        match s.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(s) => NaiveDateTime::parse_from_str(s, FORMAT)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Time-of-day `hh:mm:ss` (IST) without a date, as in the trade book's
+/// `order_timestamp`; `null` or `""` deserialize to `None`.
+pub(crate) mod kite_clock {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%H:%M:%S";
+
+    pub fn serialize<S: Serializer>(
+        v: &Option<NaiveTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(t) => serializer.serialize_str(&t.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveTime>, D::Error> {
+        let s: Option<String> = Option::deserialize(deserializer)?;
+        match s.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(s) => NaiveTime::parse_from_str(s, FORMAT)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// One entry of the order book (`GET /orders`) or of an order's history
+/// (`GET /orders/{order_id}`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Order {
+    pub order_id: String,
+    pub exchange_order_id: Option<String>,
+    pub parent_order_id: Option<String>,
+    pub placed_by: Option<String>,
+    pub account_id: Option<String>,
+    pub variety: Option<Variety>,
+    pub status: OrderStatus,
+    pub status_message: Option<String>,
+    pub status_message_raw: Option<String>,
+    #[serde(default, with = "kite_time")]
+    pub order_timestamp: Option<chrono::NaiveDateTime>,
+    #[serde(default, with = "kite_time")]
+    pub exchange_update_timestamp: Option<chrono::NaiveDateTime>,
+    #[serde(default, with = "kite_time")]
+    pub exchange_timestamp: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub modified: bool,
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub instrument_token: i32,
+    pub order_type: OrderType,
+    pub transaction_type: TransactionType,
+    pub validity: Option<Validity>,
+    pub validity_ttl: Option<u32>,
+    pub product: Product,
+    pub quantity: u32,
+    pub disclosed_quantity: u32,
+    pub price: f64,
+    pub trigger_price: f64,
+    pub average_price: f64,
+    pub filled_quantity: u32,
+    pub pending_quantity: u32,
+    pub cancelled_quantity: u32,
+    pub market_protection: Option<f64>,
+    pub tag: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub guid: Option<String>,
+    #[serde(default)]
+    pub meta: Option<serde_json::Value>,
+}
+
+/// One fill from the trade book (`GET /trades`, `GET /orders/{order_id}/trades`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Trade {
+    pub trade_id: String,
+    pub order_id: String,
+    pub exchange_order_id: Option<String>,
+    pub tradingsymbol: String,
+    pub exchange: String,
+    pub instrument_token: i32,
+    pub transaction_type: TransactionType,
+    pub product: Product,
+    pub average_price: f64,
+    pub quantity: u32,
+    #[serde(default, with = "kite_time")]
+    pub fill_timestamp: Option<chrono::NaiveDateTime>,
+    /// Time of day only (`hh:mm:ss`); the date is that of `fill_timestamp`.
+    #[serde(default, with = "kite_clock")]
+    pub order_timestamp: Option<chrono::NaiveTime>,
+    #[serde(default, with = "kite_time")]
+    pub exchange_timestamp: Option<chrono::NaiveDateTime>,
+}
+
+/// One row of `GET /portfolio/positions`.
+///
+/// `quantity` is signed: negative for net short positions.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Position {
+    pub tradingsymbol: String,
+    pub exchange: String,
+    pub instrument_token: i32,
+    pub product: Product,
+    pub quantity: i64,
+    pub overnight_quantity: i64,
+    pub multiplier: f64,
+    pub average_price: f64,
+    pub close_price: f64,
+    pub last_price: f64,
+    pub value: f64,
+    pub pnl: f64,
+    pub m2m: f64,
+    pub unrealised: f64,
+    pub realised: f64,
+    pub buy_quantity: i64,
+    pub buy_price: f64,
+    pub buy_value: f64,
+    pub buy_m2m: f64,
+    pub sell_quantity: i64,
+    pub sell_price: f64,
+    pub sell_value: f64,
+    pub sell_m2m: f64,
+    pub day_buy_quantity: i64,
+    pub day_buy_price: f64,
+    pub day_buy_value: f64,
+    pub day_sell_quantity: i64,
+    pub day_sell_price: f64,
+    pub day_sell_value: f64,
+}
+
+/// Kite returns positions split into two views:
+/// - `net`: the actual current net position (overnight + today)
+/// - `day`: only today's activity
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Positions {
+    pub net: Vec<Position>,
+    pub day: Vec<Position>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionType {
+    Day,
+    Overnight,
+}
+
+/// Form body for `PUT /portfolio/positions` (e.g. MIS -> NRML).
+#[derive(Debug, Clone, Serialize)]
+pub struct ConvertPositionParams {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub transaction_type: TransactionType,
+    pub position_type: PositionType,
+    pub quantity: u32,
+    pub old_product: Product,
+    pub new_product: Product,
+}
+
+impl std::str::FromStr for TransactionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "BUY" => Ok(TransactionType::Buy),
+            "SELL" => Ok(TransactionType::Sell),
+            other => Err(format!("invalid transaction_type: {other}")),
+        }
+    }
+}
+
+impl std::str::FromStr for Product {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "CNC" => Ok(Product::Cnc),
+            "NRML" => Ok(Product::Nrml),
+            "MIS" => Ok(Product::Mis),
+            "MTF" => Ok(Product::Mtf),
+            "CO" => Ok(Product::Co),
+            "BO" => Ok(Product::Bo),
+            other => Err(format!("invalid product: {other}")),
+        }
+    }
+}
+
+impl std::str::FromStr for PositionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "day" => Ok(PositionType::Day),
+            "overnight" => Ok(PositionType::Overnight),
+            other => Err(format!("invalid position_type: {other}")),
+        }
+    }
+}
+
+/// Margin segment for `GET /user/margins/{segment}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarginSegment {
+    Equity,
+    Commodity,
+}
+
+impl MarginSegment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarginSegment::Equity => "equity",
+            MarginSegment::Commodity => "commodity",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AvailableMargin {
+    pub adhoc_margin: f64,
+    pub cash: f64,
+    pub opening_balance: f64,
+    pub live_balance: f64,
+    pub collateral: f64,
+    pub intraday_payin: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UtilisedMargin {
+    pub debits: f64,
+    pub exposure: f64,
+    pub m2m_realised: f64,
+    pub m2m_unrealised: f64,
+    pub option_premium: f64,
+    pub payout: f64,
+    pub span: f64,
+    pub holding_sales: f64,
+    pub turnover: f64,
+    pub liquid_collateral: f64,
+    pub stock_collateral: f64,
+    pub delivery: f64,
+}
+
+/// Funds and margin usage for one segment.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SegmentMargins {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub net: f64,
+    #[serde(default)]
+    pub available: AvailableMargin,
+    #[serde(default)]
+    pub utilised: UtilisedMargin,
+}
+
+/// Response of `GET /user/margins` (both segments).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Margins {
+    pub equity: Option<SegmentMargins>,
+    pub commodity: Option<SegmentMargins>,
+}
+
+/// One prospective order sent to the margin calculator.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarginOrderParams {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub transaction_type: TransactionType,
+    pub variety: Variety,
+    pub product: Product,
+    pub order_type: OrderType,
+    pub quantity: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MarginPnl {
+    pub realised: f64,
+    pub unrealised: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MarginGst {
+    pub igst: f64,
+    pub cgst: f64,
+    pub sgst: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MarginCharges {
+    pub transaction_tax: f64,
+    pub transaction_tax_type: String,
+    pub exchange_turnover_charge: f64,
+    pub sebi_turnover_charge: f64,
+    pub brokerage: f64,
+    pub stamp_duty: f64,
+    pub gst: MarginGst,
+    pub total: f64,
+}
+
+/// Margin requirement for one order (or a basket total).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OrderMargin {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub tradingsymbol: String,
+    pub exchange: String,
+    pub span: f64,
+    pub exposure: f64,
+    pub option_premium: f64,
+    pub additional: f64,
+    pub bo: f64,
+    pub cash: f64,
+    pub var: f64,
+    pub pnl: MarginPnl,
+    pub leverage: f64,
+    pub charges: MarginCharges,
+    pub total: f64,
+}
+
+/// Response of `POST /margins/basket`.
+///
+/// `initial` is the plain sum of the legs; `final` accounts for hedging
+/// benefits across the basket (and open positions, if requested).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BasketMargins {
+    pub initial: OrderMargin,
+    #[serde(rename = "final")]
+    pub final_: OrderMargin,
+    #[serde(default)]
+    pub orders: Vec<OrderMargin>,
+    #[serde(default)]
+    pub charges: Option<MarginCharges>,
+}
+
+/// Kite REST quote timestamps (`yyyy-mm-dd hh:mm:ss`, IST) to UNIX seconds,
+/// matching what the ticker sends in binary frames.
+fn ist_to_unix_secs(t: Option<chrono::NaiveDateTime>) -> Option<u32> {
+    const IST_OFFSET_SECS: i64 = 5 * 3600 + 30 * 60;
+    t.map(|t| t.timestamp() - IST_OFFSET_SECS)
+        .and_then(|s| u32::try_from(s).ok())
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct RawOhlc {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl From<RawOhlc> for crate::ticks::Ohlc {
+    fn from(o: RawOhlc) -> Self {
+        Self {
+            open: o.open,
+            high: o.high,
+            low: o.low,
+            close: o.close,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct RawDepthLevel {
+    pub price: f64,
+    pub quantity: u32,
+    pub orders: u16,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct RawDepth {
+    pub buy: Vec<RawDepthLevel>,
+    pub sell: Vec<RawDepthLevel>,
+}
+
+impl From<RawDepth> for crate::ticks::MarketDepth {
+    fn from(d: RawDepth) -> Self {
+        use crate::ticks::DepthLevel;
+        fn side(levels: &[RawDepthLevel]) -> [DepthLevel; 5] {
+            let mut out = [DepthLevel {
+                quantity: 0,
+                price: 0.0,
+                orders: 0,
+            }; 5];
+            for (slot, l) in out.iter_mut().zip(levels) {
+                *slot = DepthLevel {
+                    quantity: l.quantity,
+                    price: l.price,
+                    orders: l.orders,
+                };
+            }
+            out
+        }
+        Self {
+            buy: side(&d.buy),
+            sell: side(&d.sell),
+        }
+    }
+}
+
+/// Entry of `GET /quote`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawQuote {
+    pub instrument_token: i32,
+    #[serde(default, with = "kite_time")]
+    pub timestamp: Option<chrono::NaiveDateTime>,
+    #[serde(default, with = "kite_time")]
+    pub last_trade_time: Option<chrono::NaiveDateTime>,
+    pub last_price: f64,
+    #[serde(default)]
+    pub last_quantity: u32,
+    #[serde(default)]
+    pub buy_quantity: u32,
+    #[serde(default)]
+    pub sell_quantity: u32,
+    #[serde(default)]
+    pub volume: u32,
+    #[serde(default)]
+    pub average_price: f64,
+    #[serde(default)]
+    pub oi: f64,
+    #[serde(default)]
+    pub oi_day_high: f64,
+    #[serde(default)]
+    pub oi_day_low: f64,
+    #[serde(default)]
+    pub ohlc: RawOhlc,
+    #[serde(default)]
+    pub depth: Option<RawDepth>,
+}
+
+impl RawQuote {
+    pub fn into_tick(self, received_ns: u64) -> crate::ticks::Tick {
+        let close = self.ohlc.close;
+        crate::ticks::Tick {
+            instrument_token: self.instrument_token,
+            mode: crate::ticks::TickMode::Full,
+            last_price: self.last_price,
+            last_quantity: Some(self.last_quantity),
+            average_traded_price: Some(self.average_price),
+            volume_traded: Some(self.volume),
+            total_buy_quantity: Some(self.buy_quantity),
+            total_sell_quantity: Some(self.sell_quantity),
+            ohlc: Some(self.ohlc.into()),
+            // Same definition the binary decoder uses for quote/full packets.
+            change: Some(if close != 0.0 {
+                (self.last_price - close) / close
+            } else {
+                0.0
+            }),
+            last_trade_time: ist_to_unix_secs(self.last_trade_time),
+            open_interest: Some(self.oi as u32),
+            oi_day_high: Some(self.oi_day_high as u32),
+            oi_day_low: Some(self.oi_day_low as u32),
+            exchange_timestamp: ist_to_unix_secs(self.timestamp),
+            depth: self.depth.map(Into::into),
+            received_ns,
+        }
+    }
+}
+
+/// Entry of `GET /quote/ohlc`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawOhlcQuote {
+    pub instrument_token: i32,
+    pub last_price: f64,
+    #[serde(default)]
+    pub ohlc: RawOhlc,
+}
+
+impl RawOhlcQuote {
+    pub fn into_tick(self, received_ns: u64) -> crate::ticks::Tick {
+        let mut tick =
+            crate::ticks::Tick::new_ltp(self.instrument_token, self.last_price, received_ns);
+        tick.mode = crate::ticks::TickMode::Quote;
+        tick.ohlc = Some(self.ohlc.into());
+        tick
+    }
+}
+
+/// Entry of `GET /quote/ltp`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawLtpQuote {
+    pub instrument_token: i32,
+    pub last_price: f64,
+}
+
+impl RawLtpQuote {
+    pub fn into_tick(self, received_ns: u64) -> crate::ticks::Tick {
+        crate::ticks::Tick::new_ltp(self.instrument_token, self.last_price, received_ns)
+    }
+}
+
+/// Candle interval for the historical data API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Interval {
+    #[serde(rename = "minute")]
+    Minute,
+    #[serde(rename = "3minute")]
+    Minute3,
+    #[serde(rename = "5minute")]
+    Minute5,
+    #[serde(rename = "10minute")]
+    Minute10,
+    #[serde(rename = "15minute")]
+    Minute15,
+    #[serde(rename = "30minute")]
+    Minute30,
+    #[serde(rename = "60minute")]
+    Minute60,
+    #[serde(rename = "day")]
+    Day,
+}
+
+impl Interval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::Minute => "minute",
+            Interval::Minute3 => "3minute",
+            Interval::Minute5 => "5minute",
+            Interval::Minute10 => "10minute",
+            Interval::Minute15 => "15minute",
+            Interval::Minute30 => "30minute",
+            Interval::Minute60 => "60minute",
+            Interval::Day => "day",
+        }
+    }
+
+    /// Longest `from..to` span (in days) Kite accepts in one request.
+    pub fn max_days_per_request(&self) -> i64 {
+        match self {
+            Interval::Minute => 60,
+            Interval::Minute3 | Interval::Minute5 | Interval::Minute10 => 100,
+            Interval::Minute15 | Interval::Minute30 => 200,
+            Interval::Minute60 => 400,
+            Interval::Day => 2000,
+        }
+    }
+}
+
+/// One OHLCV candle. `oi` is only present when requested.
+#[derive(Debug, Clone)]
+pub struct Candle {
+    pub timestamp: chrono::DateTime<chrono::FixedOffset>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+    pub oi: Option<u64>,
+}
+
+/// Response of `GET /instruments/historical/...`.
+///
+/// Candles are positional arrays: `[timestamp, open, high, low, close, volume, oi?]`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawCandles {
+    pub candles: Vec<Vec<serde_json::Value>>,
+}
+
+impl RawCandles {
+    pub fn into_candles(self) -> Result<Vec<Candle>, String> {
+        self.candles
+            .into_iter()
+            .map(|row| {
+                let ts = row
+                    .first()
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("candle without timestamp: {row:?}"))?;
+                let timestamp = chrono::DateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%z")
+                    .map_err(|e| format!("invalid candle timestamp {ts}: {e}"))?;
+                let num = |i: usize| -> Result<f64, String> {
+                    row.get(i)
+                        .and_then(|v| v.as_f64())
+                        .ok_or_else(|| format!("candle field {i} missing: {row:?}"))
+                };
+                Ok(Candle {
+                    timestamp,
+                    open: num(1)?,
+                    high: num(2)?,
+                    low: num(3)?,
+                    close: num(4)?,
+                    volume: num(5)? as u64,
+                    oi: row.get(6).and_then(|v| v.as_f64()).map(|v| v as u64),
+                })
+            })
+            .collect()
+    }
+}
+
+/// GTT trigger kind: one trigger price, or an OCO pair (stoploss + target).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum GttType {
+    #[serde(rename = "single")]
+    Single,
+    #[serde(rename = "two-leg")]
+    TwoLeg,
+}
+
+impl GttType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GttType::Single => "single",
+            GttType::TwoLeg => "two-leg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GttStatus {
+    Active,
+    Triggered,
+    Disabled,
+    Expired,
+    Cancelled,
+    Rejected,
+    Deleted,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GttCondition {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    /// One value for `single`, `[lower, upper]` for `two-leg`.
+    pub trigger_values: Vec<f64>,
+    /// LTP when the trigger was created; Kite uses it to pick the trigger direction.
+    pub last_price: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrument_token: Option<i32>,
+}
+
+/// Order placed when a GTT fires.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GttOrder {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub transaction_type: TransactionType,
+    pub quantity: u32,
+    pub order_type: OrderType,
+    pub product: Product,
+    pub price: f64,
+    /// Outcome of the order once the trigger fired (absent while active).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+}
+
+/// A GTT trigger as returned by `GET /gtt/triggers`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GttTrigger {
+    pub id: i64,
+    pub user_id: Option<String>,
+    pub parent_trigger: Option<i64>,
+    #[serde(rename = "type")]
+    pub kind: GttType,
+    pub status: GttStatus,
+    #[serde(default, with = "kite_time")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(default, with = "kite_time")]
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    #[serde(default, with = "kite_time")]
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    pub condition: GttCondition,
+    pub orders: Vec<GttOrder>,
+    #[serde(default)]
+    pub meta: Option<serde_json::Value>,
+}
+
+/// Trigger definition for creating or modifying a GTT.
+#[derive(Debug, Clone)]
+pub struct GttParams {
+    pub kind: GttType,
+    pub condition: GttCondition,
+    pub orders: Vec<GttOrder>,
+}
+
+impl GttParams {
+    /// Single-leg trigger: place `order` when the price crosses `trigger_price`.
+    pub fn single(
+        exchange: impl Into<String>,
+        tradingsymbol: impl Into<String>,
+        last_price: f64,
+        trigger_price: f64,
+        order: GttLeg,
+    ) -> Self {
+        let exchange = exchange.into();
+        let tradingsymbol = tradingsymbol.into();
+        Self {
+            kind: GttType::Single,
+            orders: vec![order.into_order(&exchange, &tradingsymbol)],
+            condition: GttCondition {
+                exchange,
+                tradingsymbol,
+                trigger_values: vec![trigger_price],
+                last_price,
+                instrument_token: None,
+            },
+        }
+    }
+
+    /// OCO trigger: one trigger below `last_price` and one above it; whichever
+    /// fires first cancels the other. For a long position the stoploss is the
+    /// lower trigger, for a short (buy-to-cover) the upper one.
+    ///
+    /// Kite wants the trigger values in ascending order, with the orders in the
+    /// same order, so the legs are sorted by trigger. Fails with
+    /// `KiteError::Input` unless the triggers straddle `last_price`.
+    pub fn oco(
+        exchange: impl Into<String>,
+        tradingsymbol: impl Into<String>,
+        last_price: f64,
+        stoploss_trigger: f64,
+        stoploss: GttLeg,
+        target_trigger: f64,
+        target: GttLeg,
+    ) -> Result<Self, KiteError> {
+        let (low, high) = if stoploss_trigger <= target_trigger {
+            ((stoploss_trigger, stoploss), (target_trigger, target))
+        } else {
+            ((target_trigger, target), (stoploss_trigger, stoploss))
+        };
+        if !(low.0 < last_price && last_price < high.0) {
+            return Err(KiteError::Input {
+                status: 0,
+                message: format!(
+                    "OCO triggers {stoploss_trigger} and {target_trigger} must straddle last price {last_price}"
+                ),
+            });
+        }
+
+        let exchange = exchange.into();
+        let tradingsymbol = tradingsymbol.into();
+        Ok(Self {
+            kind: GttType::TwoLeg,
+            orders: vec![
+                low.1.into_order(&exchange, &tradingsymbol),
+                high.1.into_order(&exchange, &tradingsymbol),
+            ],
+            condition: GttCondition {
+                exchange,
+                tradingsymbol,
+                trigger_values: vec![low.0, high.0],
+                last_price,
+                instrument_token: None,
+            },
+        })
+    }
+}
+
+/// The order side of one GTT leg; exchange/symbol come from the condition.
+#[derive(Debug, Clone, Copy)]
+pub struct GttLeg {
+    pub transaction_type: TransactionType,
+    pub quantity: u32,
+    pub product: Product,
+    pub price: f64,
+}
+
+impl GttLeg {
+    fn into_order(self, exchange: &str, tradingsymbol: &str) -> GttOrder {
+        GttOrder {
+            exchange: exchange.to_string(),
+            tradingsymbol: tradingsymbol.to_string(),
+            transaction_type: self.transaction_type,
+            quantity: self.quantity,
+            // Kite only accepts LIMIT orders in GTTs.
+            order_type: OrderType::Limit,
+            product: self.product,
+            price: self.price,
+            result: None,
+        }
+    }
+}
+
+/// Response of GTT create/modify/delete.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GttResponse {
+    pub trigger_id: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    #[test]
+    fn trade_book_entry_deserializes() {
+        // Trimmed from a live `GET /trades` response.
+        let trade: Trade = serde_json::from_str(
+            r#"{
+                "trade_id": "10000000",
+                "order_id": "200000000000000",
+                "exchange": "NSE",
+                "tradingsymbol": "SBIN",
+                "instrument_token": 779521,
+                "product": "CNC",
+                "average_price": 420.65,
+                "quantity": 1,
+                "exchange_order_id": "300000000000000",
+                "transaction_type": "BUY",
+                "fill_timestamp": "2021-05-31 09:16:39",
+                "order_timestamp": "09:16:39",
+                "exchange_timestamp": "2021-05-31 09:16:39"
+            }"#,
+        )
+        .unwrap();
+
+        let fill = NaiveDate::from_ymd_opt(2021, 5, 31)
+            .unwrap()
+            .and_hms_opt(9, 16, 39)
+            .unwrap();
+        assert_eq!(trade.fill_timestamp, Some(fill));
+        assert_eq!(trade.exchange_timestamp, Some(fill));
+        assert_eq!(trade.order_timestamp, NaiveTime::from_hms_opt(9, 16, 39));
+
+        let json = serde_json::to_value(&trade).unwrap();
+        assert_eq!(json["order_timestamp"], "09:16:39");
+    }
+
+    #[test]
+    fn empty_trade_timestamps_are_none() {
+        let trade: Trade = serde_json::from_str(
+            r#"{
+                "trade_id": "1", "order_id": "2", "exchange": "NFO",
+                "tradingsymbol": "NIFTY24JUNFUT", "instrument_token": 13238786,
+                "product": "NRML", "average_price": 23465.6, "quantity": 25,
+                "transaction_type": "SELL", "fill_timestamp": null,
+                "order_timestamp": "", "exchange_timestamp": null
+            }"#,
+        )
+        .unwrap();
+        assert!(trade.order_timestamp.is_none());
+        assert!(trade.fill_timestamp.is_none());
+    }
+
+    fn leg(transaction_type: TransactionType, price: f64) -> GttLeg {
+        GttLeg {
+            transaction_type,
+            quantity: 10,
+            product: Product::Cnc,
+            price,
+        }
+    }
+
+    fn legs(params: &GttParams) -> Vec<(f64, TransactionType, f64)> {
+        params
+            .condition
+            .trigger_values
+            .iter()
+            .zip(&params.orders)
+            .map(|(t, o)| (*t, o.transaction_type, o.price))
+            .collect()
+    }
+
+    #[test]
+    fn long_oco_keeps_stoploss_as_the_lower_leg() {
+        let sell = TransactionType::Sell;
+        let params = GttParams::oco(
+            "NSE",
+            "INFY",
+            1500.0,
+            1400.0,
+            leg(sell, 1395.0),
+            1650.0,
+            leg(sell, 1655.0),
+        )
+        .unwrap();
+        assert_eq!(
+            legs(&params),
+            vec![(1400.0, sell, 1395.0), (1650.0, sell, 1655.0)]
+        );
+    }
+
+    #[test]
+    fn short_oco_sends_triggers_in_ascending_order() {
+        let buy = TransactionType::Buy;
+        // Buy-to-cover: stoploss above the market, target below it.
+        let params = GttParams::oco(
+            "NSE",
+            "INFY",
+            1500.0,
+            1600.0,
+            leg(buy, 1605.0),
+            1350.0,
+            leg(buy, 1345.0),
+        )
+        .unwrap();
+        assert_eq!(
+            legs(&params),
+            vec![(1350.0, buy, 1345.0), (1600.0, buy, 1605.0)]
+        );
+    }
+
+    #[test]
+    fn oco_triggers_must_straddle_last_price() {
+        let sell = TransactionType::Sell;
+        for (stoploss, target) in [(1400.0, 1450.0), (1550.0, 1650.0), (1500.0, 1650.0)] {
+            let err = GttParams::oco(
+                "NSE",
+                "INFY",
+                1500.0,
+                stoploss,
+                leg(sell, stoploss),
+                target,
+                leg(sell, target),
+            )
+            .unwrap_err();
+            assert!(matches!(err, KiteError::Input { status: 0, .. }), "{err}");
+        }
+    }
+}
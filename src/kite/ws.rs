@@ -1,14 +1,20 @@
 use crate::core::AppError;
-use crate::ticks::{decode_binary_ticks, now_unix_ns, TickStore};
+use crate::kite::error::KiteError;
+use crate::kite::types::Order;
+use crate::ticks::{now_unix_ns, visit_binary_ticks, TickMode, TickStore, UnknownPacket};
+use futures_util::future::BoxFuture;
 use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::{sleep, Duration};
-use tokio_tungstenite::tungstenite::http::header::HeaderValue;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::HeaderValue;
 use tokio_tungstenite::tungstenite::Message;
-use tracing::{debug, info, warn};
+use tracing::{debug, info, warn, Instrument};
 
 fn env_bool_default(key: &str, default: bool) -> bool {
     let Some(v) = std::env::var(key).ok() else {
@@ -28,7 +34,9 @@ fn env_bool_default(key: &str, default: bool) -> bool {
 }
 
 fn env_u64(key: &str) -> Option<u64> {
-    std::env::var(key).ok().and_then(|v| v.trim().parse::<u64>().ok())
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
 }
 
 #[derive(Clone, Debug)]
@@ -55,83 +63,478 @@ impl TickLogConfig {
     }
 }
 
+/// Subscription change requested through a [`TickerControl`] handle.
+#[derive(Debug, Clone)]
+pub enum TickerCommand {
+    Subscribe { tokens: Vec<i32>, mode: TickMode },
+    Unsubscribe { tokens: Vec<i32> },
+    SetMode { tokens: Vec<i32>, mode: TickMode },
+}
+
+/// Non-tick message pushed by the ticker as a JSON text frame.
+#[derive(Debug, Clone)]
+pub enum TickerEvent {
+    /// Order postback (`{"type":"order"}`): placement, modification, fills, cancellation.
+    OrderUpdate(Box<Order>),
+    /// Server-side error (`{"type":"error"}`), e.g. an invalid or expired access token.
+    Error(String),
+    /// Informational broker message (`{"type":"message"}`).
+    Message(String),
+}
+
+/// Buffered events per subscriber before it starts lagging.
+pub(crate) const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Deserialize)]
+struct RawTextFrame {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+/// Parse a ticker text frame. Unknown frame types and malformed payloads yield `Err`
+/// with a description so the caller can log them.
+pub fn parse_text_frame(txt: &str) -> Result<TickerEvent, String> {
+    let raw: RawTextFrame =
+        serde_json::from_str(txt).map_err(|e| format!("invalid text frame: {e}"))?;
+    let as_text = |v: serde_json::Value| match v {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+    match raw.kind.as_str() {
+        "order" => serde_json::from_value::<Order>(raw.data)
+            .map(|o| TickerEvent::OrderUpdate(Box::new(o)))
+            .map_err(|e| format!("invalid order update: {e}")),
+        "error" => Ok(TickerEvent::Error(as_text(raw.data))),
+        "message" => Ok(TickerEvent::Message(as_text(raw.data))),
+        other => Err(format!("unknown text frame type: {other}")),
+    }
+}
+
+/// Messages Kite sends with a `TokenException` when the session is no longer valid.
+const TOKEN_ERROR_MESSAGES: &[&str] = &[
+    "Incorrect `api_key` or `access_token`.",
+    "Invalid `api_key` or `access_token`.",
+    "Token is invalid or has expired.",
+    "Invalid session credentials",
+];
+
+/// Whether an error frame means the session is no longer valid: its data is
+/// a Kite error object with `error_type` `TokenException`, or its text is one
+/// of [`TOKEN_ERROR_MESSAGES`] (ignoring case and a trailing period).
+///
+/// Other errors that merely mention the api_key or access_token (connection
+/// limits, bad subscriptions) must not trigger a token refresh.
+fn is_token_error_message(msg: &str) -> bool {
+    let msg = msg.trim();
+    if let Ok(serde_json::Value::Object(obj)) = serde_json::from_str(msg) {
+        return obj.get("error_type").and_then(|v| v.as_str()) == Some("TokenException");
+    }
+    let normalize = |s: &str| s.trim().trim_end_matches('.').to_ascii_lowercase();
+    let msg = normalize(msg);
+    TOKEN_ERROR_MESSAGES.iter().any(|m| normalize(m) == msg)
+}
+
+/// Fetches a replacement access token after the ticker hits an auth failure
+/// (handshake 403 or a token error frame).
+///
+/// Called with the rejected token. Returns the token to connect with next, or
+/// `None` when no fresh token is available yet; returning the rejected token
+/// again counts as `None`.
+pub type TokenRefresher =
+    Arc<dyn Fn(String) -> BoxFuture<'static, Result<Option<String>, AppError>> + Send + Sync>;
+
+/// Connection state shared between the read loop and [`TickerControl`].
+#[derive(Debug, Default)]
+struct TickerStatus {
+    connected: AtomicBool,
+    subscribed: AtomicUsize,
+    connects: AtomicU64,
+    /// Reconnects forced because no frame arrived within the read timeout.
+    stale_reconnects: AtomicU64,
+    read_timeout_ms: AtomicU64,
+    last_message_ns: AtomicU64,
+    last_heartbeat_ns: AtomicU64,
+    last_tick_ns: AtomicU64,
+    last_error: Mutex<Option<String>>,
+    /// Packet lengths seen that match no known layout (each is warned about once).
+    unknown_packet_lengths: Mutex<BTreeSet<usize>>,
+    counters: TickerCounters,
+}
+
+/// Monotonic feed counters (see [`TickerStats`]).
+#[derive(Debug, Default)]
+struct TickerCounters {
+    binary_frames: AtomicU64,
+    heartbeats: AtomicU64,
+    text_frames: AtomicU64,
+    bytes: AtomicU64,
+    packets: AtomicU64,
+    unknown_packets: AtomicU64,
+    ticks: AtomicU64,
+    filtered_ticks: AtomicU64,
+    reconnects: AtomicU64,
+    backoff_ms: AtomicU64,
+}
+
+/// Snapshot of a ticker's feed counters. All counts are totals since start;
+/// diff two snapshots with [`TickerStats::rates_since`] for per-second rates.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TickerStats {
+    /// Binary frames carrying tick packets (heartbeats excluded).
+    pub binary_frames: u64,
+    pub heartbeats: u64,
+    pub text_frames: u64,
+    /// Payload bytes of all binary and text frames.
+    pub bytes: u64,
+    pub packets: u64,
+    /// Packets dropped because their length matched no known layout.
+    pub unknown_packets: u64,
+    /// Ticks applied to the store.
+    pub ticks: u64,
+    /// Ticks discarded because their token is not subscribed.
+    pub filtered_ticks: u64,
+    pub reconnects: u64,
+    /// Total time spent sleeping between reconnect attempts.
+    pub backoff_ms: u64,
+    /// When the snapshot was taken (UNIX ns).
+    pub at_ns: u64,
+}
+
+/// Per-second rates between two [`TickerStats`] snapshots.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TickerRates {
+    pub frames_per_s: f64,
+    pub bytes_per_s: f64,
+    pub packets_per_s: f64,
+    pub ticks_per_s: f64,
+}
+
+impl TickerStats {
+    /// Average packets per binary frame.
+    pub fn packets_per_frame(&self) -> f64 {
+        if self.binary_frames == 0 {
+            0.0
+        } else {
+            self.packets as f64 / self.binary_frames as f64
+        }
+    }
+
+    pub fn rates_since(&self, earlier: &TickerStats) -> TickerRates {
+        let dt_s = self.at_ns.saturating_sub(earlier.at_ns) as f64 / 1_000_000_000.0;
+        if dt_s <= 0.0 {
+            return TickerRates::default();
+        }
+        let rate = |now: u64, then: u64| now.saturating_sub(then) as f64 / dt_s;
+        TickerRates {
+            frames_per_s: rate(self.binary_frames, earlier.binary_frames),
+            bytes_per_s: rate(self.bytes, earlier.bytes),
+            packets_per_s: rate(self.packets, earlier.packets),
+            ticks_per_s: rate(self.ticks, earlier.ticks),
+        }
+    }
+
+    /// Sum of two snapshots, e.g. across pool shards.
+    pub fn merge(self, other: TickerStats) -> TickerStats {
+        TickerStats {
+            binary_frames: self.binary_frames + other.binary_frames,
+            heartbeats: self.heartbeats + other.heartbeats,
+            text_frames: self.text_frames + other.text_frames,
+            bytes: self.bytes + other.bytes,
+            packets: self.packets + other.packets,
+            unknown_packets: self.unknown_packets + other.unknown_packets,
+            ticks: self.ticks + other.ticks,
+            filtered_ticks: self.filtered_ticks + other.filtered_ticks,
+            reconnects: self.reconnects + other.reconnects,
+            backoff_ms: self.backoff_ms + other.backoff_ms,
+            at_ns: self.at_ns.max(other.at_ns),
+        }
+    }
+}
+
+/// Point-in-time health of one ticker connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickerHealth {
+    /// Handshake done and subscriptions replayed; false while reconnecting.
+    pub connected: bool,
+    pub subscribed_tokens: usize,
+    /// Successful connections so far (1 + reconnects).
+    pub connects: u64,
+    /// Connected and a frame arrived within the read timeout.
+    pub alive: bool,
+    pub stale_reconnects: u64,
+    /// When the last websocket message of any kind arrived (UNIX ns).
+    pub last_message_ns: Option<u64>,
+    /// Last 1-byte heartbeat frame (UNIX ns).
+    pub last_heartbeat_ns: Option<u64>,
+    /// Last binary frame that carried at least one tick (UNIX ns).
+    pub last_tick_ns: Option<u64>,
+    /// Most recent connection error, kept after recovery for diagnostics.
+    pub last_error: Option<String>,
+    /// Packet lengths the decoder does not understand; counted in [`TickerStats::unknown_packets`].
+    pub unknown_packet_lengths: Vec<usize>,
+}
+
+/// Cloneable handle for a running ticker: changes subscriptions, reports
+/// health and hands out [`TickerEvent`] receivers.
+///
+/// Commands are applied by the read loop on the live connection; while the
+/// socket is down they queue up and are folded into the replayed subscription
+/// set on reconnect.
+#[derive(Clone, Debug)]
+pub struct TickerControl {
+    tx: mpsc::UnboundedSender<TickerCommand>,
+    events: broadcast::Sender<TickerEvent>,
+    status: Arc<TickerStatus>,
+}
+
+impl TickerControl {
+    pub fn stats(&self) -> TickerStats {
+        let c = &self.status.counters;
+        let get = |v: &AtomicU64| v.load(Ordering::Relaxed);
+        TickerStats {
+            binary_frames: get(&c.binary_frames),
+            heartbeats: get(&c.heartbeats),
+            text_frames: get(&c.text_frames),
+            bytes: get(&c.bytes),
+            packets: get(&c.packets),
+            unknown_packets: get(&c.unknown_packets),
+            ticks: get(&c.ticks),
+            filtered_ticks: get(&c.filtered_ticks),
+            reconnects: get(&c.reconnects),
+            backoff_ms: get(&c.backoff_ms),
+            at_ns: now_unix_ns(),
+        }
+    }
+
+    pub fn health(&self) -> TickerHealth {
+        let s = &self.status;
+        let ns = |v: &AtomicU64| Some(v.load(Ordering::Relaxed)).filter(|v| *v > 0);
+        let connected = s.connected.load(Ordering::Relaxed);
+        let last_message_ns = ns(&s.last_message_ns);
+        let read_timeout_ns = s.read_timeout_ms.load(Ordering::Relaxed) * 1_000_000;
+        let alive = connected
+            && last_message_ns
+                .map(|t| now_unix_ns().saturating_sub(t) <= read_timeout_ns)
+                .unwrap_or(false);
+        TickerHealth {
+            connected,
+            alive,
+            subscribed_tokens: s.subscribed.load(Ordering::Relaxed),
+            connects: s.connects.load(Ordering::Relaxed),
+            stale_reconnects: s.stale_reconnects.load(Ordering::Relaxed),
+            last_message_ns,
+            last_heartbeat_ns: ns(&s.last_heartbeat_ns),
+            last_tick_ns: ns(&s.last_tick_ns),
+            last_error: s
+                .last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            unknown_packet_lengths: s
+                .unknown_packet_lengths
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Receive order updates, errors and broker messages from the ticker.
+    ///
+    /// Only events published after this call are delivered. A receiver that
+    /// falls more than 256 events behind gets `RecvError::Lagged`.
+    pub fn events(&self) -> broadcast::Receiver<TickerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe `tokens` in `mode` (re-subscribing an existing token just changes its mode).
+    pub fn subscribe(&self, tokens: Vec<i32>, mode: TickMode) -> Result<(), AppError> {
+        self.send(TickerCommand::Subscribe { tokens, mode })
+    }
+
+    pub fn unsubscribe(&self, tokens: Vec<i32>) -> Result<(), AppError> {
+        self.send(TickerCommand::Unsubscribe { tokens })
+    }
+
+    /// Change the mode of already-subscribed tokens; unknown tokens are ignored.
+    pub fn set_mode(&self, tokens: Vec<i32>, mode: TickMode) -> Result<(), AppError> {
+        self.send(TickerCommand::SetMode { tokens, mode })
+    }
+
+    fn send(&self, cmd: TickerCommand) -> Result<(), AppError> {
+        self.tx
+            .send(cmd)
+            .map_err(|_| AppError::KiteApi("kite ticker is not running".to_string()))
+    }
+}
+
+/// Kite sends a heartbeat about once a second on an idle connection, so a
+/// silence this long means the connection is dead (e.g. half-open TCP).
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Zerodha Kite ticker websocket client.
 ///
 /// Responsibilities:
-/// - Connect to `wss://ws.kite.trade` using api_key + access_token
-/// - Subscribe to tokens and set mode=full
+/// - Connect to the ticker host (`wss://ws.kite.trade` by default) using api_key + access_token
+/// - Subscribe to the initial tokens in mode=full
+/// - Apply subscribe/unsubscribe/mode changes sent through [`TickerControl`]
 /// - Decode incoming binary tick frames
 /// - Upsert latest tick per token into `TickStore`
-/// - Reconnect with backoff on disconnect/error
-#[derive(Clone)]
+/// - Track heartbeats and force a reconnect when no frame arrives within the read timeout
+/// - Reconnect with backoff on disconnect/error, replaying the current subscriptions
+/// - On auth failure, swap in a fresh access token from the optional [`TokenRefresher`]
 pub struct KiteTickerWs {
+    ws_url: String,
     api_key: String,
     access_token: String,
-    tokens: Arc<Vec<i32>>,
-    allowed: Arc<HashSet<i32>>,
+    /// Desired subscriptions (token -> mode); replayed after every reconnect.
+    desired: HashMap<i32, TickMode>,
+    /// Tokens whose ticks are accepted into the store; tracks `desired`.
+    allowed: HashSet<i32>,
     store: Arc<TickStore>,
     log: TickLogConfig,
+    read_timeout: Duration,
+    control: TickerControl,
+    commands: mpsc::UnboundedReceiver<TickerCommand>,
+    refresher: Option<TokenRefresher>,
 }
 
 impl KiteTickerWs {
     pub fn new(
+        ws_url: String,
         api_key: String,
         access_token: String,
         tokens: Vec<i32>,
         store: Arc<TickStore>,
         log: TickLogConfig,
     ) -> Self {
-        let allowed: HashSet<i32> = tokens.iter().copied().collect();
+        let desired: HashMap<i32, TickMode> = tokens.iter().map(|t| (*t, TickMode::Full)).collect();
+        let allowed: HashSet<i32> = desired.keys().copied().collect();
+        let (tx, commands) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let status = Arc::new(TickerStatus::default());
+        status.subscribed.store(desired.len(), Ordering::Relaxed);
+        status
+            .read_timeout_ms
+            .store(DEFAULT_READ_TIMEOUT.as_millis() as u64, Ordering::Relaxed);
         Self {
+            ws_url,
             api_key,
             access_token,
-            tokens: Arc::new(tokens),
-            allowed: Arc::new(allowed),
+            desired,
+            allowed,
             store,
             log,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            control: TickerControl { tx, events, status },
+            commands,
+            refresher: None,
         }
     }
 
-    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
-        tokio::spawn(async move {
-            if let Err(e) = self.run_forever().await {
-                warn!(error = %e, "kite ticker ws exited");
+    /// Recover from an expired session by reconnecting with the token `refresher` returns.
+    pub fn with_token_refresher(mut self, refresher: TokenRefresher) -> Self {
+        self.refresher = Some(refresher);
+        self
+    }
+
+    /// Reconnect when no frame (tick, heartbeat or text) arrives for this long.
+    /// Also bounds the connect + websocket handshake.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self.control
+            .status
+            .read_timeout_ms
+            .store(read_timeout.as_millis() as u64, Ordering::Relaxed);
+        self
+    }
+
+    /// Handle for changing subscriptions at runtime. Take it before `spawn`.
+    pub fn control(&self) -> TickerControl {
+        self.control.clone()
+    }
+
+    /// Run the connection on a new task, inside the caller's current tracing span.
+    pub fn spawn(mut self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(
+            async move {
+                if let Err(e) = self.run_forever().await {
+                    warn!(error = %e, "kite ticker ws exited");
+                }
             }
-        })
+            .in_current_span(),
+        )
     }
 
-    async fn run_forever(&self) -> Result<(), AppError> {
+    async fn run_forever(&mut self) -> Result<(), AppError> {
         // Exponential backoff reconnect strategy.
         let mut backoff = Duration::from_millis(250);
         let max_backoff = Duration::from_secs(30);
 
         loop {
-            match self.run_once().await {
+            let result = self.run_once().await;
+            self.control
+                .status
+                .connected
+                .store(false, Ordering::Relaxed);
+            self.control
+                .status
+                .counters
+                .reconnects
+                .fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = &result {
+                *self
+                    .control
+                    .status
+                    .last_error
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+            }
+            match result {
                 Ok(()) => {
                     // A clean close still reconnects (server can drop idle connections).
                     backoff = Duration::from_millis(250);
                 }
                 Err(e) => {
+                    // A stale token fails every reconnect; swap it before retrying.
+                    if e.is_kite_token_error() && self.refresh_access_token().await {
+                        backoff = Duration::from_millis(250);
+                        continue;
+                    }
                     warn!(error = %e, sleep_ms = backoff.as_millis() as u64, "kite ws error; reconnecting");
                     sleep(backoff).await;
+                    self.control
+                        .status
+                        .counters
+                        .backoff_ms
+                        .fetch_add(backoff.as_millis() as u64, Ordering::Relaxed);
                     backoff = (backoff * 2).min(max_backoff);
                 }
             }
         }
     }
 
-    async fn run_once(&self) -> Result<(), AppError> {
-        if self.tokens.is_empty() {
-            return Err(AppError::KiteApi("no tokens to subscribe".to_string()));
+    async fn run_once(&mut self) -> Result<(), AppError> {
+        // Fold in commands that arrived while disconnected; they are sent as
+        // part of the replay below.
+        while let Ok(cmd) = self.commands.try_recv() {
+            self.apply_command(cmd);
         }
 
         let url = format!(
-            "wss://ws.kite.trade/?api_key={}&access_token={}",
+            "{}/?api_key={}&access_token={}",
+            self.ws_url.trim_end_matches('/'),
             urlencoding::encode(&self.api_key),
             urlencoding::encode(&self.access_token)
         );
 
-        info!(token_count = self.tokens.len(), "connecting kite ticker websocket");
+        info!(
+            token_count = self.desired.len(),
+            "connecting kite ticker websocket"
+        );
         let mut req = url
             .into_client_request()
             .map_err(|e| AppError::KiteApi(format!("ws request build failed: {e}")))?;
@@ -146,63 +549,148 @@ impl KiteTickerWs {
             "User-Agent",
             HeaderValue::from_static("zatamap-trade-rust/0.1"),
         );
-        req.headers_mut().insert(
-            "X-Kite-Version",
-            HeaderValue::from_static("3"),
-        );
+        req.headers_mut()
+            .insert("X-Kite-Version", HeaderValue::from_static("3"));
 
-        let (ws_stream, resp) = tokio_tungstenite::connect_async(req)
+        // A handshake that never completes (e.g. a black-holed TCP connect) would
+        // otherwise hang here forever; time it out like a stale feed and reconnect.
+        let connect = tokio_tungstenite::connect_async(req);
+        let (ws_stream, resp) = tokio::time::timeout(self.read_timeout, connect)
             .await
-            .map_err(|e| AppError::KiteApi(format!("ws connect failed: {e}")))?;
+            .map_err(|_| {
+                AppError::KiteApi(format!(
+                    "ws connect timed out after {} ms",
+                    self.read_timeout.as_millis()
+                ))
+            })?
+            .map_err(|e| match e {
+                // Kite rejects the upgrade with 403 when the access token is invalid/expired.
+                tokio_tungstenite::tungstenite::Error::Http(resp)
+                    if matches!(resp.status().as_u16(), 401 | 403) =>
+                {
+                    AppError::Kite(KiteError::Token {
+                        status: resp.status().as_u16(),
+                        message: "ticker handshake rejected the access token".to_string(),
+                    })
+                }
+                e => AppError::KiteApi(format!("ws connect failed: {e}")),
+            })?;
 
         info!(status = %resp.status(), "kite ws connected");
 
         let (mut write, mut read) = ws_stream.split();
 
-        self.subscribe_full(&mut write).await?;
-        info!(token_count = self.tokens.len(), "subscribed + mode=full");
+        self.replay_subscriptions(&mut write).await?;
+        info!(token_count = self.desired.len(), "subscriptions replayed");
+        self.control.status.connected.store(true, Ordering::Relaxed);
+        self.control.status.connects.fetch_add(1, Ordering::Relaxed);
 
         let log_full_ticks = self.log.enabled;
         let log_interval = self.log.interval;
         let mut last_tick_log = std::time::Instant::now();
         let mut logged_first_per_token: HashSet<i32> = HashSet::new();
 
-        // Read loop: decode binary ticks; log server messages.
-        while let Some(msg) = read.next().await {
+        // Read loop: decode binary ticks; log server messages; apply control commands.
+        // A read that hangs past the timeout (half-open connection) forces a reconnect.
+        let mut last_frame = tokio::time::Instant::now();
+        loop {
+            let msg = tokio::select! {
+                msg = read.next() => msg,
+                Some(cmd) = self.commands.recv() => {
+                    let cmd = self.apply_command(cmd);
+                    send_command(&mut write, &cmd).await?;
+                    continue;
+                }
+                _ = tokio::time::sleep_until(last_frame + self.read_timeout) => {
+                    self.control.status.stale_reconnects.fetch_add(1, Ordering::Relaxed);
+                    return Err(AppError::KiteApi(format!(
+                        "ws feed stale: no frames for {} ms",
+                        self.read_timeout.as_millis()
+                    )));
+                }
+            };
+            let Some(msg) = msg else {
+                break;
+            };
+            last_frame = tokio::time::Instant::now();
+            let received_ns = now_unix_ns();
+            self.control
+                .status
+                .last_message_ns
+                .store(received_ns, Ordering::Relaxed);
+            let counters = &self.control.status.counters;
             match msg {
+                // Kite's heartbeat is a single-byte binary frame.
+                Ok(Message::Binary(bin)) if bin.len() == 1 => {
+                    counters.heartbeats.fetch_add(1, Ordering::Relaxed);
+                    counters.bytes.fetch_add(1, Ordering::Relaxed);
+                    self.control
+                        .status
+                        .last_heartbeat_ns
+                        .store(received_ns, Ordering::Relaxed);
+                }
                 Ok(Message::Binary(bin)) => {
-                    let received_ns = now_unix_ns();
-                    let ticks = decode_binary_ticks(&bin, received_ns);
-                    if !ticks.is_empty() {
-                        for t in ticks {
-                            // Defensive: keep memory bounded even if server sends
-                            // unexpected tokens.
-                            if self.allowed.contains(&t.instrument_token) {
-                                if log_full_ticks {
-                                    let first_for_token = logged_first_per_token.insert(t.instrument_token);
-                                    let due = last_tick_log.elapsed() >= log_interval;
-                                    if first_for_token || due {
-                                        let symbol = self
-                                            .store
-                                            .get_symbol(t.instrument_token)
-                                            .unwrap_or_else(|| Arc::<str>::from(""));
-                                        info!(
-                                            instrument_token = t.instrument_token,
-                                            tradingsymbol = %symbol,
-                                            tick = ?t,
-                                            "kite tick"
-                                        );
-                                        last_tick_log = std::time::Instant::now();
-                                    }
-                                }
-                                self.store.update_tick(t);
+                    let mut applied = 0u64;
+                    let mut filtered = 0u64;
+                    let frame = visit_binary_ticks(&bin, |packet| {
+                        // Defensive: keep memory bounded even if server sends
+                        // unexpected tokens. Checked before reading any other field.
+                        let token = packet.instrument_token();
+                        if !self.allowed.contains(&token) {
+                            filtered += 1;
+                            return;
+                        }
+                        if log_full_ticks {
+                            let first_for_token = logged_first_per_token.insert(token);
+                            let due = last_tick_log.elapsed() >= log_interval;
+                            if first_for_token || due {
+                                let symbol = self
+                                    .store
+                                    .get_symbol(token)
+                                    .unwrap_or_else(|| Arc::<str>::from(""));
+                                info!(
+                                    instrument_token = token,
+                                    tradingsymbol = %symbol,
+                                    tick = ?packet.to_tick(received_ns),
+                                    "kite tick"
+                                );
+                                last_tick_log = std::time::Instant::now();
                             }
                         }
+                        applied += 1;
+                        self.store.update_packet(&packet, received_ns);
+                    });
+                    counters.binary_frames.fetch_add(1, Ordering::Relaxed);
+                    counters
+                        .bytes
+                        .fetch_add(bin.len() as u64, Ordering::Relaxed);
+                    counters
+                        .packets
+                        .fetch_add(frame.packets as u64, Ordering::Relaxed);
+                    counters
+                        .unknown_packets
+                        .fetch_add(frame.unknown_length as u64, Ordering::Relaxed);
+                    counters.ticks.fetch_add(applied, Ordering::Relaxed);
+                    counters
+                        .filtered_ticks
+                        .fetch_add(filtered, Ordering::Relaxed);
+                    if let Some(unknown) = frame.first_unknown {
+                        self.report_unknown_packet(unknown, frame.unknown_length);
+                    }
+                    if applied + filtered > 0 {
+                        self.control
+                            .status
+                            .last_tick_ns
+                            .store(received_ns, Ordering::Relaxed);
                     }
                 }
                 Ok(Message::Text(txt)) => {
-                    // Kite sends JSON control frames (error/order/connection).
-                    debug!(message = %txt, "kite ws text");
+                    counters.text_frames.fetch_add(1, Ordering::Relaxed);
+                    counters
+                        .bytes
+                        .fetch_add(txt.len() as u64, Ordering::Relaxed);
+                    // Kite sends JSON frames for order postbacks, errors and broker messages.
+                    self.publish_text_frame(&txt)?;
                 }
                 Ok(Message::Ping(p)) => {
                     // tungstenite will auto-handle ping/pong in many cases, but we can be explicit.
@@ -226,25 +714,390 @@ impl KiteTickerWs {
         Ok(())
     }
 
-    async fn subscribe_full(
+    /// Publish a text frame as a [`TickerEvent`]. Returns a token error for
+    /// auth error frames so the caller drops the connection and refreshes.
+    fn publish_text_frame(&self, txt: &str) -> Result<(), AppError> {
+        let event = match parse_text_frame(txt) {
+            Ok(ev) => ev,
+            Err(e) => {
+                debug!(error = %e, message = %txt, "kite ws text ignored");
+                return Ok(());
+            }
+        };
+        let token_error = match &event {
+            TickerEvent::Error(msg) if is_token_error_message(msg) => Some(msg.clone()),
+            _ => None,
+        };
+        match &event {
+            TickerEvent::OrderUpdate(o) => info!(
+                order_id = %o.order_id,
+                status = ?o.status,
+                filled_quantity = o.filled_quantity,
+                "kite ws order update"
+            ),
+            TickerEvent::Error(msg) => warn!(message = %msg, "kite ws error frame"),
+            TickerEvent::Message(msg) => info!(message = %msg, "kite ws message"),
+        }
+        // No subscribers is fine; the event is simply dropped.
+        let _ = self.control.events.send(event);
+
+        match token_error {
+            Some(message) => Err(AppError::Kite(KiteError::Token {
+                status: 403,
+                message,
+            })),
+            None => Ok(()),
+        }
+    }
+
+    /// Warn the first time a packet length shows up that the decoder does not
+    /// know; repeats are only logged at debug (they are still counted).
+    fn report_unknown_packet(&self, unknown: UnknownPacket, in_frame: usize) {
+        let first_seen = self
+            .control
+            .status
+            .unknown_packet_lengths
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(unknown.len);
+        if first_seen {
+            warn!(
+                len = unknown.len,
+                instrument_token = ?unknown.instrument_token,
+                in_frame = in_frame,
+                "ws packet with unknown layout skipped"
+            );
+        } else {
+            debug!(
+                len = unknown.len,
+                instrument_token = ?unknown.instrument_token,
+                in_frame = in_frame,
+                "ws packet with unknown layout skipped"
+            );
+        }
+    }
+
+    /// Ask the refresher for a new access token. True if one was installed.
+    async fn refresh_access_token(&mut self) -> bool {
+        let Some(refresher) = self.refresher.clone() else {
+            return false;
+        };
+        match refresher(self.access_token.clone()).await {
+            Ok(Some(token)) if !token.is_empty() && token != self.access_token => {
+                info!(access_token_tail4 = %token_tail4(&token), "kite ws access token refreshed");
+                self.access_token = token;
+                true
+            }
+            Ok(_) => {
+                warn!("kite ws token refresh found no new access token");
+                false
+            }
+            Err(e) => {
+                warn!(error = %e, "kite ws token refresh failed");
+                false
+            }
+        }
+    }
+
+    /// Update the desired subscription set (and the tick allow-list) for `cmd`,
+    /// and return the command to send on the wire: a mode change only for
+    /// tokens that are subscribed.
+    fn apply_command(&mut self, cmd: TickerCommand) -> TickerCommand {
+        let cmd = match cmd {
+            TickerCommand::SetMode { tokens, mode } => {
+                let requested = tokens.len();
+                let tokens: Vec<i32> = tokens
+                    .into_iter()
+                    .filter(|t| self.desired.contains_key(t))
+                    .collect();
+                let ignored = requested - tokens.len();
+                if ignored > 0 {
+                    debug!(ignored, "kite ws set mode: skipping unsubscribed tokens");
+                }
+                TickerCommand::SetMode { tokens, mode }
+            }
+            cmd => cmd,
+        };
+        match &cmd {
+            TickerCommand::Subscribe { tokens, mode } => {
+                for t in tokens {
+                    self.desired.insert(*t, *mode);
+                    self.allowed.insert(*t);
+                }
+                info!(
+                    count = tokens.len(),
+                    mode = mode.as_str(),
+                    "kite ws subscribe"
+                );
+            }
+            TickerCommand::Unsubscribe { tokens } => {
+                for t in tokens {
+                    self.desired.remove(t);
+                    self.allowed.remove(t);
+                }
+                info!(count = tokens.len(), "kite ws unsubscribe");
+            }
+            TickerCommand::SetMode { tokens, mode } => {
+                for t in tokens {
+                    self.desired.insert(*t, *mode);
+                }
+                info!(
+                    count = tokens.len(),
+                    mode = mode.as_str(),
+                    "kite ws set mode"
+                );
+            }
+        }
+        self.control
+            .status
+            .subscribed
+            .store(self.desired.len(), Ordering::Relaxed);
+        cmd
+    }
+
+    /// Subscribe every desired token, grouped by mode.
+    async fn replay_subscriptions(
         &self,
         write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
     ) -> Result<(), AppError> {
-        // Subscribe in chunks to keep message sizes reasonable.
-        const CHUNK: usize = 300;
-        for chunk in self.tokens.chunks(CHUNK) {
-            let msg = json!({"a":"subscribe","v":chunk});
-            write
-                .send(Message::Text(msg.to_string()))
-                .await
-                .map_err(|e| AppError::KiteApi(format!("ws subscribe send failed: {e}")))?;
-
-            let mode_msg = json!({"a":"mode","v":["full", chunk]});
-            write
-                .send(Message::Text(mode_msg.to_string()))
-                .await
-                .map_err(|e| AppError::KiteApi(format!("ws mode send failed: {e}")))?;
+        for mode in [TickMode::Ltp, TickMode::Quote, TickMode::Full] {
+            let mut tokens: Vec<i32> = self
+                .desired
+                .iter()
+                .filter(|(_, m)| **m == mode)
+                .map(|(t, _)| *t)
+                .collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            tokens.sort_unstable();
+            send_command(write, &TickerCommand::Subscribe { tokens, mode }).await?;
         }
         Ok(())
     }
 }
+
+fn token_tail4(token: &str) -> String {
+    let n = token.chars().count();
+    token.chars().skip(n.saturating_sub(4)).collect()
+}
+
+/// Write the ticker protocol messages for `cmd`.
+async fn send_command(
+    write: &mut (impl SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin),
+    cmd: &TickerCommand,
+) -> Result<(), AppError> {
+    // Send in chunks to keep message sizes reasonable.
+    const CHUNK: usize = 300;
+    let msgs: Vec<serde_json::Value> = match cmd {
+        TickerCommand::Subscribe { tokens, mode } => tokens
+            .chunks(CHUNK)
+            .flat_map(|chunk| {
+                [
+                    json!({"a":"subscribe","v":chunk}),
+                    json!({"a":"mode","v":[mode.as_str(), chunk]}),
+                ]
+            })
+            .collect(),
+        TickerCommand::Unsubscribe { tokens } => tokens
+            .chunks(CHUNK)
+            .map(|chunk| json!({"a":"unsubscribe","v":chunk}))
+            .collect(),
+        TickerCommand::SetMode { tokens, mode } => tokens
+            .chunks(CHUNK)
+            .map(|chunk| json!({"a":"mode","v":[mode.as_str(), chunk]}))
+            .collect(),
+    };
+    for msg in msgs {
+        write
+            .send(Message::Text(msg.to_string()))
+            .await
+            .map_err(|e| AppError::KiteApi(format!("ws control send failed: {e}")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kite::types::OrderStatus;
+    use std::convert::Infallible;
+    use tokio_tungstenite::tungstenite::Error as WsError;
+
+    fn ticker(tokens: Vec<i32>) -> KiteTickerWs {
+        KiteTickerWs::new(
+            "ws://127.0.0.1:1".to_string(),
+            "key".to_string(),
+            "token".to_string(),
+            tokens,
+            Arc::new(TickStore::default()),
+            TickLogConfig::from_env(),
+        )
+    }
+
+    /// The JSON messages `send_command` writes for `cmd`.
+    async fn sent(cmd: TickerCommand) -> Vec<serde_json::Value> {
+        let mut sink =
+            Vec::<Message>::new().sink_map_err(|e: Infallible| -> WsError { match e {} });
+        send_command(&mut sink, &cmd).await.unwrap();
+        sink.into_inner()
+            .into_iter()
+            .map(|m| serde_json::from_str(m.to_text().unwrap()).unwrap())
+            .collect()
+    }
+
+    fn sizes(msgs: &[serde_json::Value]) -> Vec<(String, usize)> {
+        msgs.iter()
+            .map(|m| {
+                let v = &m["v"];
+                let tokens = if m["a"] == "mode" { &v[1] } else { v };
+                (
+                    m["a"].as_str().unwrap().to_string(),
+                    tokens.as_array().unwrap().len(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn set_mode_only_touches_subscribed_tokens() {
+        let mut ws = ticker(vec![1, 2]);
+        let cmd = ws.apply_command(TickerCommand::SetMode {
+            tokens: vec![2, 3, 4],
+            mode: TickMode::Ltp,
+        });
+        let TickerCommand::SetMode { tokens, mode } = cmd else {
+            panic!("expected a mode change, got {cmd:?}");
+        };
+        assert_eq!((tokens, mode), (vec![2], TickMode::Ltp));
+        assert_eq!(ws.desired.len(), 2);
+        assert_eq!(ws.desired[&1], TickMode::Full);
+        assert_eq!(ws.desired[&2], TickMode::Ltp);
+        assert_eq!(ws.control.status.subscribed.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn commands_are_sent_in_chunks_of_300_tokens() {
+        let tokens = |n: i32| (0..n).collect::<Vec<i32>>();
+        let msgs = sent(TickerCommand::Subscribe {
+            tokens: tokens(700),
+            mode: TickMode::Quote,
+        })
+        .await;
+        let expected: Vec<(String, usize)> = [300, 300, 100]
+            .into_iter()
+            .flat_map(|n| [("subscribe".to_string(), n), ("mode".to_string(), n)])
+            .collect();
+        assert_eq!(sizes(&msgs), expected);
+        assert_eq!(msgs[1]["v"][0], "quote");
+        assert_eq!(msgs[4]["v"][0], 600);
+
+        let msgs = sent(TickerCommand::Unsubscribe {
+            tokens: tokens(600),
+        })
+        .await;
+        assert_eq!(
+            sizes(&msgs),
+            vec![
+                ("unsubscribe".to_string(), 300),
+                ("unsubscribe".to_string(), 300)
+            ]
+        );
+
+        let msgs = sent(TickerCommand::SetMode {
+            tokens: tokens(301),
+            mode: TickMode::Full,
+        })
+        .await;
+        assert_eq!(
+            sizes(&msgs),
+            vec![("mode".to_string(), 300), ("mode".to_string(), 1)]
+        );
+        assert_eq!(msgs[1]["v"], json!(["full", [300]]));
+
+        let msgs = sent(TickerCommand::Subscribe {
+            tokens: vec![],
+            mode: TickMode::Full,
+        })
+        .await;
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn order_postback_parses_into_an_order_update() {
+        let frame = include_str!("../../fixtures/ticker/order_update.json");
+        let TickerEvent::OrderUpdate(order) = parse_text_frame(frame).unwrap() else {
+            panic!("expected an order update");
+        };
+        assert_eq!(order.order_id, "220303000308932");
+        assert_eq!(order.status, OrderStatus::Complete);
+        assert_eq!(order.tradingsymbol, "SBIN");
+        assert_eq!(order.instrument_token, 779521);
+        assert_eq!((order.filled_quantity, order.average_price), (1, 470.0));
+        assert_eq!(
+            order.exchange_update_timestamp.unwrap().to_string(),
+            "2022-03-03 09:24:25"
+        );
+    }
+
+    #[test]
+    fn error_and_message_frames_carry_their_text() {
+        let frame = include_str!("../../fixtures/ticker/error.json");
+        let TickerEvent::Error(msg) = parse_text_frame(frame).unwrap() else {
+            panic!("expected an error frame");
+        };
+        assert_eq!(msg, "Token is invalid or has expired.");
+        assert!(is_token_error_message(&msg));
+
+        let frame = include_str!("../../fixtures/ticker/message.json");
+        let TickerEvent::Message(msg) = parse_text_frame(frame).unwrap() else {
+            panic!("expected a message frame");
+        };
+        assert!(msg.starts_with("Market data will be delayed"));
+    }
+
+    #[test]
+    fn malformed_text_frames_are_errors() {
+        for (frame, err) in [
+            ("not json", "invalid text frame"),
+            (
+                r#"{"type":"order","data":{"order_id":"1"}}"#,
+                "invalid order update",
+            ),
+            (
+                r#"{"type":"instruments_meta","data":{}}"#,
+                "unknown text frame type",
+            ),
+        ] {
+            let got = parse_text_frame(frame).unwrap_err();
+            assert!(got.starts_with(err), "{frame}: {got}");
+        }
+    }
+
+    #[test]
+    fn token_error_frames_are_recognised() {
+        for msg in [
+            "Incorrect `api_key` or `access_token`.",
+            "Invalid `api_key` or `access_token`",
+            "token is invalid or has expired.",
+            "  Invalid session credentials  ",
+            r#"{"error_type": "TokenException", "message": "Session expired"}"#,
+        ] {
+            assert!(is_token_error_message(msg), "{msg}");
+        }
+    }
+
+    #[test]
+    fn other_error_frames_are_not_token_errors() {
+        for msg in [
+            "Maximum allowed connections for this api_key exceeded.",
+            "Invalid instrument token in subscribe request; check access_token scope",
+            "Token is invalid or has expired. Retrying",
+            "TokenException",
+            r#"{"error_type": "InputException", "message": "Invalid `api_key` or `access_token`."}"#,
+            "",
+        ] {
+            assert!(!is_token_error_message(msg), "{msg}");
+        }
+    }
+}
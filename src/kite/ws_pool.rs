@@ -0,0 +1,947 @@
+use crate::core::AppError;
+use crate::kite::types::Order;
+use crate::kite::ws::{
+    KiteTickerWs, TickLogConfig, TickerControl, TickerEvent, TickerHealth, TickerStats,
+    TokenRefresher, DEFAULT_READ_TIMEOUT, EVENT_CHANNEL_CAPACITY,
+};
+use crate::ticks::{now_unix_ns, TickMode, TickStore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, info_span, warn};
+
+fn env_usize(key: &str) -> Option<usize> {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+}
+
+#[derive(Clone, Debug)]
+pub struct TickerPoolConfig {
+    pub max_tokens_per_connection: usize,
+    pub max_connections: usize,
+    /// Rebalance once the busiest and idlest shards differ by more than this many tokens.
+    pub rebalance_threshold: usize,
+    /// Per-shard staleness window (see [`KiteTickerWs::with_read_timeout`]).
+    pub read_timeout: Duration,
+}
+
+impl Default for TickerPoolConfig {
+    /// Kite limits: 3000 instruments per connection, 3 connections per API key.
+    fn default() -> Self {
+        Self {
+            max_tokens_per_connection: 3000,
+            max_connections: 3,
+            rebalance_threshold: 300,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+        }
+    }
+}
+
+impl TickerPoolConfig {
+    /// Env:
+    /// - KITE_WS_MAX_TOKENS_PER_CONN (default 3000)
+    /// - KITE_WS_MAX_CONNECTIONS (default 3)
+    /// - KITE_WS_REBALANCE_THRESHOLD (default 300)
+    /// - KITE_WS_READ_TIMEOUT_MS (default 10000)
+    pub fn from_env() -> Self {
+        let d = Self::default();
+        Self {
+            max_tokens_per_connection: env_usize("KITE_WS_MAX_TOKENS_PER_CONN")
+                .filter(|v| *v > 0)
+                .unwrap_or(d.max_tokens_per_connection),
+            max_connections: env_usize("KITE_WS_MAX_CONNECTIONS")
+                .filter(|v| *v > 0)
+                .unwrap_or(d.max_connections),
+            rebalance_threshold: env_usize("KITE_WS_REBALANCE_THRESHOLD")
+                .unwrap_or(d.rebalance_threshold),
+            read_timeout: env_usize("KITE_WS_READ_TIMEOUT_MS")
+                .filter(|v| *v > 0)
+                .map(|v| Duration::from_millis(v as u64))
+                .unwrap_or(d.read_timeout),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.max_tokens_per_connection * self.max_connections
+    }
+}
+
+/// Health of one pool shard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardHealth {
+    pub shard: usize,
+    #[serde(flatten)]
+    pub health: TickerHealth,
+}
+
+/// Feed health as reported by `/api/health`.
+///
+/// The ticker process can also write it to a file (`TICKER_HEALTH_FILE`) so a
+/// server running in another process reports the same feed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedHealth {
+    pub alive: bool,
+    pub subscribed_tokens: usize,
+    pub shards: Vec<ShardHealth>,
+    /// When the snapshot was taken (UNIX ms).
+    pub updated_at_ms: u64,
+    /// Why a snapshot could not be used (missing, unreadable or stale).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FeedHealth {
+    /// Write the snapshot to `path`, via a temp file and rename so readers
+    /// never see a partial write.
+    pub fn write_snapshot(&self, path: &Path) -> Result<(), AppError> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| AppError::KiteApi(format!("feed health encode: {e}")))?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| AppError::KiteApi(format!("feed health write {}: {e}", path.display())))
+    }
+
+    /// Read a snapshot written by [`FeedHealth::write_snapshot`]. A snapshot
+    /// older than `max_age` means the ticker stopped updating it, so it is
+    /// reported as not alive; read errors likewise.
+    pub fn read_snapshot(path: &Path, max_age: Duration) -> Self {
+        let snapshot = std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|b| serde_json::from_slice::<FeedHealth>(&b).map_err(|e| e.to_string()));
+        let mut feed = match snapshot {
+            Ok(feed) => feed,
+            Err(e) => {
+                return Self {
+                    alive: false,
+                    subscribed_tokens: 0,
+                    shards: Vec::new(),
+                    updated_at_ms: 0,
+                    error: Some(format!("{}: {e}", path.display())),
+                }
+            }
+        };
+        let age_ms = (now_unix_ns() / 1_000_000).saturating_sub(feed.updated_at_ms);
+        if age_ms > max_age.as_millis() as u64 {
+            feed.alive = false;
+            feed.error = Some(format!("snapshot is {age_ms} ms old"));
+        }
+        feed
+    }
+}
+
+/// Order updates already forwarded by [`KiteTickerPool::events`] are
+/// remembered this many at a time.
+const ORDER_DEDUP_WINDOW: usize = 1024;
+
+/// Identity of one order postback. Kite pushes each update on every
+/// connection, and the copies are identical. Exchange times only have
+/// second resolution, so the fill state is part of the key: two partial
+/// fills in the same second are different updates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct OrderUpdateKey {
+    order_id: String,
+    status: String,
+    order_timestamp: Option<chrono::NaiveDateTime>,
+    exchange_update_timestamp: Option<chrono::NaiveDateTime>,
+    filled_quantity: u32,
+    pending_quantity: u32,
+    /// `f64::to_bits` of the average fill price.
+    average_price: u64,
+}
+
+impl From<&Order> for OrderUpdateKey {
+    fn from(order: &Order) -> Self {
+        Self {
+            order_id: order.order_id.clone(),
+            status: String::from(order.status.clone()),
+            order_timestamp: order.order_timestamp,
+            exchange_update_timestamp: order.exchange_update_timestamp,
+            filled_quantity: order.filled_quantity,
+            pending_quantity: order.pending_quantity,
+            average_price: order.average_price.to_bits(),
+        }
+    }
+}
+
+/// Recently forwarded order updates, oldest first, so the merged event stream
+/// carries each postback once however many shards received it.
+#[derive(Debug, Default)]
+struct OrderUpdateDedup {
+    seen: HashSet<OrderUpdateKey>,
+    order: VecDeque<OrderUpdateKey>,
+}
+
+impl OrderUpdateDedup {
+    /// True the first time `order`'s update is seen within the window.
+    fn first_sighting(&mut self, order: &Order) -> bool {
+        let key = OrderUpdateKey::from(order);
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > ORDER_DEDUP_WINDOW {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+/// Subscription change to send on one shard connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShardOp {
+    Subscribe {
+        shard: usize,
+        tokens: Vec<i32>,
+        mode: TickMode,
+    },
+    Unsubscribe {
+        shard: usize,
+        tokens: Vec<i32>,
+    },
+    SetMode {
+        shard: usize,
+        tokens: Vec<i32>,
+        mode: TickMode,
+    },
+}
+
+/// Which shard each token streams on, and in which mode.
+///
+/// Pure bookkeeping: each change returns the [`ShardOp`]s that bring the
+/// connections in line, and `shards.len()` is how many connections it needs.
+#[derive(Debug, Default)]
+struct Placement {
+    /// Tokens per shard, with their mode.
+    shards: Vec<HashMap<i32, TickMode>>,
+    /// token -> shard index
+    assignments: HashMap<i32, usize>,
+}
+
+impl Placement {
+    /// Place `tokens` in `mode`: already-placed tokens keep their shard and
+    /// switch mode, new ones go to the least-loaded shard after adding just
+    /// enough shards for the new total. Fails without changing anything when
+    /// the total would exceed the pool capacity.
+    fn subscribe(
+        &mut self,
+        tokens: &[i32],
+        mode: TickMode,
+        config: &TickerPoolConfig,
+    ) -> Result<Vec<ShardOp>, AppError> {
+        let mut new_tokens: Vec<i32> = tokens
+            .iter()
+            .copied()
+            .filter(|t| !self.assignments.contains_key(t))
+            .collect();
+        new_tokens.sort_unstable();
+        new_tokens.dedup();
+
+        let total = self.assignments.len() + new_tokens.len();
+        if total > config.capacity() {
+            return Err(AppError::KiteApi(format!(
+                "ticker pool capacity exceeded: {total} tokens > {} connections x {} tokens",
+                config.max_connections, config.max_tokens_per_connection
+            )));
+        }
+
+        // Existing tokens: mode change on their current shard.
+        let mut by_shard: BTreeMap<usize, Vec<i32>> = BTreeMap::new();
+        for t in tokens {
+            if let Some(&idx) = self.assignments.get(t) {
+                self.shards[idx].insert(*t, mode);
+                by_shard.entry(idx).or_default().push(*t);
+            }
+        }
+
+        // Add just enough shards for the new total, then fill the least loaded.
+        let per_conn = config.max_tokens_per_connection;
+        let needed = ((total + per_conn - 1) / per_conn).max(1);
+        while self.shards.len() < needed {
+            self.shards.push(HashMap::new());
+        }
+        for t in new_tokens {
+            let idx = self.least_loaded();
+            self.assignments.insert(t, idx);
+            self.shards[idx].insert(t, mode);
+            by_shard.entry(idx).or_default().push(t);
+        }
+
+        let mut ops: Vec<ShardOp> = by_shard
+            .into_iter()
+            .map(|(shard, tokens)| ShardOp::Subscribe {
+                shard,
+                tokens,
+                mode,
+            })
+            .collect();
+        // A freshly added shard starts empty next to full ones.
+        ops.extend(self.rebalance(config.rebalance_threshold));
+        Ok(ops)
+    }
+
+    fn unsubscribe(&mut self, tokens: &[i32], config: &TickerPoolConfig) -> Vec<ShardOp> {
+        let mut by_shard: BTreeMap<usize, Vec<i32>> = BTreeMap::new();
+        for t in tokens {
+            if let Some(idx) = self.assignments.remove(t) {
+                self.shards[idx].remove(t);
+                by_shard.entry(idx).or_default().push(*t);
+            }
+        }
+        let mut ops: Vec<ShardOp> = by_shard
+            .into_iter()
+            .map(|(shard, tokens)| ShardOp::Unsubscribe { shard, tokens })
+            .collect();
+        ops.extend(self.rebalance(config.rebalance_threshold));
+        ops
+    }
+
+    /// Change the mode of placed tokens; unknown tokens are ignored.
+    fn set_mode(&mut self, tokens: &[i32], mode: TickMode) -> Vec<ShardOp> {
+        let mut by_shard: BTreeMap<usize, Vec<i32>> = BTreeMap::new();
+        for t in tokens {
+            if let Some(&idx) = self.assignments.get(t) {
+                self.shards[idx].insert(*t, mode);
+                by_shard.entry(idx).or_default().push(*t);
+            }
+        }
+        by_shard
+            .into_iter()
+            .map(|(shard, tokens)| ShardOp::SetMode {
+                shard,
+                tokens,
+                mode,
+            })
+            .collect()
+    }
+
+    /// Move tokens from the busiest to the idlest shard until they are within
+    /// `threshold` of each other.
+    fn rebalance(&mut self, threshold: usize) -> Vec<ShardOp> {
+        let mut ops = Vec::new();
+        if self.shards.len() < 2 {
+            return ops;
+        }
+        loop {
+            let busiest = self.most_loaded();
+            let idlest = self.least_loaded();
+            let gap = self.shards[busiest].len() - self.shards[idlest].len();
+            if gap <= threshold.max(1) {
+                return ops;
+            }
+
+            let mut moving: Vec<(i32, TickMode)> =
+                self.shards[busiest].iter().map(|(t, m)| (*t, *m)).collect();
+            moving.sort_unstable_by_key(|(t, _)| *t);
+            moving.truncate(gap / 2);
+
+            let mut by_mode: BTreeMap<&'static str, (TickMode, Vec<i32>)> = BTreeMap::new();
+            for (t, m) in &moving {
+                by_mode
+                    .entry(m.as_str())
+                    .or_insert((*m, Vec::new()))
+                    .1
+                    .push(*t);
+            }
+            for (mode, tokens) in by_mode.into_values() {
+                ops.push(ShardOp::Subscribe {
+                    shard: idlest,
+                    tokens,
+                    mode,
+                });
+            }
+            ops.push(ShardOp::Unsubscribe {
+                shard: busiest,
+                tokens: moving.iter().map(|(t, _)| *t).collect(),
+            });
+
+            for (t, m) in moving {
+                self.shards[busiest].remove(&t);
+                self.shards[idlest].insert(t, m);
+                self.assignments.insert(t, idlest);
+            }
+            info!(
+                from = busiest,
+                to = idlest,
+                moved = gap / 2,
+                "ticker pool rebalanced"
+            );
+        }
+    }
+
+    fn least_loaded(&self) -> usize {
+        (0..self.shards.len())
+            .min_by_key(|i| self.shards[*i].len())
+            .unwrap_or(0)
+    }
+
+    fn most_loaded(&self) -> usize {
+        (0..self.shards.len())
+            .max_by_key(|i| self.shards[*i].len())
+            .unwrap_or(0)
+    }
+}
+
+struct Shard {
+    control: TickerControl,
+    handle: tokio::task::JoinHandle<()>,
+    /// Forwards this shard's events into the pool's merged stream.
+    events_forwarder: tokio::task::JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Open connections, indexed like `placement.shards`.
+    shards: Vec<Shard>,
+    placement: Placement,
+}
+
+/// Ticker that spreads tokens over several websocket connections.
+///
+/// All shards write into the same `TickStore`. Shards are opened on demand,
+/// just enough of them for the subscribed tokens, up to `max_connections`.
+/// New tokens go to the least-loaded shard. When a subscribe or unsubscribe
+/// leaves the shards uneven by more than `rebalance_threshold`, tokens are moved from the
+/// busiest to the idlest shard. The two connections apply the move
+/// independently, so a moved token can miss ticks (or arrive on both) for a
+/// moment. Shards are not closed when they empty out.
+/// Dropping the pool stops every shard.
+pub struct KiteTickerPool {
+    ws_url: String,
+    api_key: String,
+    /// Latest known-good token; shards opened later start with it.
+    access_token: Arc<Mutex<String>>,
+    store: Arc<TickStore>,
+    log: TickLogConfig,
+    config: TickerPoolConfig,
+    refresher: Option<TokenRefresher>,
+    /// Merged, de-duplicated events of every shard.
+    events: broadcast::Sender<TickerEvent>,
+    order_dedup: Arc<Mutex<OrderUpdateDedup>>,
+    state: Mutex<PoolState>,
+}
+
+impl KiteTickerPool {
+    pub fn new(
+        ws_url: String,
+        api_key: String,
+        access_token: String,
+        store: Arc<TickStore>,
+        log: TickLogConfig,
+        config: TickerPoolConfig,
+    ) -> Self {
+        Self {
+            ws_url,
+            api_key,
+            access_token: Arc::new(Mutex::new(access_token)),
+            store,
+            log,
+            config,
+            refresher: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            order_dedup: Arc::default(),
+            state: Mutex::new(PoolState::default()),
+        }
+    }
+
+    /// Token refresher used by every shard (see [`KiteTickerWs::with_token_refresher`]).
+    pub fn with_token_refresher(mut self, refresher: TokenRefresher) -> Self {
+        let latest = self.access_token.clone();
+        self.refresher = Some(Arc::new(move |rejected: String| {
+            let fut = refresher(rejected);
+            let latest = latest.clone();
+            Box::pin(async move {
+                let token = fut.await?;
+                if let Some(t) = &token {
+                    *latest.lock().unwrap_or_else(|e| e.into_inner()) = t.clone();
+                }
+                Ok(token)
+            })
+        }));
+        self
+    }
+
+    /// Subscribe `tokens` in `mode`. Already-subscribed tokens keep their shard
+    /// and switch to `mode`. Fails without changing anything if the pool would
+    /// exceed `max_connections * max_tokens_per_connection`.
+    pub fn subscribe(&self, tokens: &[i32], mode: TickMode) -> Result<(), AppError> {
+        let mut state = self.lock_state();
+        let ops = state.placement.subscribe(tokens, mode, &self.config)?;
+        self.apply(&mut state, ops)
+    }
+
+    pub fn unsubscribe(&self, tokens: &[i32]) -> Result<(), AppError> {
+        let mut state = self.lock_state();
+        let ops = state.placement.unsubscribe(tokens, &self.config);
+        self.apply(&mut state, ops)
+    }
+
+    /// Change the mode of subscribed tokens; unknown tokens are ignored.
+    pub fn set_mode(&self, tokens: &[i32], mode: TickMode) -> Result<(), AppError> {
+        let mut state = self.lock_state();
+        let ops = state.placement.set_mode(tokens, mode);
+        self.apply(&mut state, ops)
+    }
+
+    pub fn subscribed_count(&self) -> usize {
+        self.lock_state().placement.assignments.len()
+    }
+
+    /// Per-shard connection health, in shard order.
+    pub fn health(&self) -> Vec<ShardHealth> {
+        self.lock_state()
+            .shards
+            .iter()
+            .enumerate()
+            .map(|(shard, s)| ShardHealth {
+                shard,
+                health: s.control.health(),
+            })
+            .collect()
+    }
+
+    /// Feed counters summed over all shards.
+    pub fn stats(&self) -> TickerStats {
+        self.lock_state()
+            .shards
+            .iter()
+            .map(|s| s.control.stats())
+            .fold(TickerStats::default(), TickerStats::merge)
+    }
+
+    /// Whole-feed health snapshot (see [`FeedHealth`]).
+    pub fn feed_health(&self) -> FeedHealth {
+        let shards = self.health();
+        FeedHealth {
+            alive: !shards.is_empty() && shards.iter().all(|s| s.health.alive),
+            subscribed_tokens: self.subscribed_count(),
+            shards,
+            updated_at_ms: now_unix_ns() / 1_000_000,
+            error: None,
+        }
+    }
+
+    /// True when at least one shard is open and every shard is alive
+    /// (connected, with a frame inside the read timeout).
+    pub fn is_alive(&self) -> bool {
+        let health = self.health();
+        !health.is_empty() && health.iter().all(|s| s.health.alive)
+    }
+
+    /// Order updates, errors and server messages from every shard, including
+    /// shards opened after the call. Kite pushes order updates on every
+    /// connection; each one is delivered once.
+    pub fn events(&self) -> broadcast::Receiver<TickerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stop every shard connection.
+    pub fn shutdown(&self) {
+        for shard in &self.lock_state().shards {
+            shard.handle.abort();
+            shard.events_forwarder.abort();
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn open_shard(&self, idx: usize) -> Shard {
+        let access_token = self
+            .access_token
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let mut ws = KiteTickerWs::new(
+            self.ws_url.clone(),
+            self.api_key.clone(),
+            access_token,
+            Vec::new(),
+            self.store.clone(),
+            self.log.clone(),
+        )
+        .with_read_timeout(self.config.read_timeout);
+        if let Some(r) = &self.refresher {
+            ws = ws.with_token_refresher(r.clone());
+        }
+        let control = ws.control();
+        let events_forwarder = tokio::spawn(forward_events(
+            idx,
+            control.events(),
+            self.events.clone(),
+            self.order_dedup.clone(),
+        ));
+        let handle = {
+            let span = info_span!("kite_ws_shard", shard = idx);
+            let _enter = span.enter();
+            ws.spawn()
+        };
+        info!(shard = idx, "ticker pool shard opened");
+        Shard {
+            control,
+            handle,
+            events_forwarder,
+        }
+    }
+
+    /// Open the shards the placement now needs, then send `ops` on them.
+    fn apply(&self, state: &mut PoolState, ops: Vec<ShardOp>) -> Result<(), AppError> {
+        while state.shards.len() < state.placement.shards.len() {
+            let shard = self.open_shard(state.shards.len());
+            state.shards.push(shard);
+        }
+        for op in ops {
+            match op {
+                ShardOp::Subscribe {
+                    shard,
+                    tokens,
+                    mode,
+                } => state.shards[shard].control.subscribe(tokens, mode)?,
+                ShardOp::Unsubscribe { shard, tokens } => {
+                    state.shards[shard].control.unsubscribe(tokens)?
+                }
+                ShardOp::SetMode {
+                    shard,
+                    tokens,
+                    mode,
+                } => state.shards[shard].control.set_mode(tokens, mode)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for KiteTickerPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Copy one shard's events into the pool stream, dropping order updates
+/// another shard already delivered.
+async fn forward_events(
+    shard: usize,
+    mut rx: broadcast::Receiver<TickerEvent>,
+    tx: broadcast::Sender<TickerEvent>,
+    dedup: Arc<Mutex<OrderUpdateDedup>>,
+) {
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!(
+                    shard = shard,
+                    skipped = n,
+                    "ticker pool event forwarder lagged"
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if let TickerEvent::OrderUpdate(order) = &event {
+            if !dedup
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .first_sighting(order)
+            {
+                continue;
+            }
+        }
+        // No subscribers is fine; the event is simply dropped.
+        let _ = tx.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(updated_at_ms: u64) -> FeedHealth {
+        FeedHealth {
+            alive: true,
+            subscribed_tokens: 2,
+            shards: vec![ShardHealth {
+                shard: 0,
+                health: TickerHealth {
+                    connected: true,
+                    subscribed_tokens: 2,
+                    connects: 1,
+                    alive: true,
+                    stale_reconnects: 0,
+                    last_message_ns: Some(1),
+                    last_heartbeat_ns: None,
+                    last_tick_ns: Some(1),
+                    last_error: None,
+                    unknown_packet_lengths: vec![],
+                },
+            }],
+            updated_at_ms,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn feed_health_snapshot_round_trips_and_goes_stale() {
+        let path = std::env::temp_dir().join(format!("feed-health-{}.json", std::process::id()));
+        let max_age = Duration::from_secs(10);
+        let now_ms = now_unix_ns() / 1_000_000;
+
+        feed(now_ms).write_snapshot(&path).unwrap();
+        let read = FeedHealth::read_snapshot(&path, max_age);
+        assert!(read.alive, "{:?}", read.error);
+        assert_eq!(read.subscribed_tokens, 2);
+        assert_eq!(read.shards[0].health.connects, 1);
+
+        feed(now_ms - 60_000).write_snapshot(&path).unwrap();
+        let stale = FeedHealth::read_snapshot(&path, max_age);
+        assert!(!stale.alive);
+        assert!(stale.error.unwrap().contains("old"));
+
+        std::fs::remove_file(&path).unwrap();
+        let missing = FeedHealth::read_snapshot(&path, max_age);
+        assert!(!missing.alive);
+        assert!(missing.shards.is_empty());
+        assert!(missing.error.is_some());
+    }
+
+    fn order_update(order_id: &str, status: &str, exchange_update: Option<&str>) -> Order {
+        serde_json::from_value(serde_json::json!({
+            "order_id": order_id,
+            "status": status,
+            "exchange_update_timestamp": exchange_update,
+            "exchange": "NSE",
+            "tradingsymbol": "SBIN",
+            "instrument_token": 779521,
+            "order_type": "LIMIT",
+            "transaction_type": "BUY",
+            "product": "CNC",
+            "quantity": 1,
+            "disclosed_quantity": 0,
+            "price": 420.65,
+            "trigger_price": 0.0,
+            "average_price": 0.0,
+            "filled_quantity": 0,
+            "pending_quantity": 1,
+            "cancelled_quantity": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn order_dedup_keeps_distinct_updates_and_forgets_old_ones() {
+        let mut dedup = OrderUpdateDedup::default();
+        let open = order_update("1", "OPEN", Some("2024-06-10 09:15:01"));
+        assert!(dedup.first_sighting(&open));
+        assert!(!dedup.first_sighting(&open));
+        // Same order, later exchange update or a new status: a new postback.
+        assert!(dedup.first_sighting(&order_update("1", "OPEN", Some("2024-06-10 09:15:02"))));
+        assert!(dedup.first_sighting(&order_update("1", "VALIDATION PENDING", None)));
+        assert!(dedup.first_sighting(&order_update("1", "OPEN PENDING", None)));
+
+        for i in 0..ORDER_DEDUP_WINDOW {
+            dedup.first_sighting(&order_update(&format!("other-{i}"), "OPEN", None));
+        }
+        assert_eq!(dedup.seen.len(), ORDER_DEDUP_WINDOW);
+        assert!(dedup.first_sighting(&open));
+    }
+
+    #[test]
+    fn partial_fills_in_the_same_second_are_distinct_updates() {
+        let mut dedup = OrderUpdateDedup::default();
+        let mut fill = order_update("1", "OPEN", Some("2024-06-10 09:15:01"));
+        fill.quantity = 10;
+        fill.filled_quantity = 3;
+        fill.pending_quantity = 7;
+        fill.average_price = 420.65;
+        let mut next = fill.clone();
+        next.filled_quantity = 5;
+        assert!(dedup.first_sighting(&fill));
+        assert!(dedup.first_sighting(&next));
+        assert!(!dedup.first_sighting(&fill.clone()));
+        assert!(!dedup.first_sighting(&next));
+    }
+
+    #[tokio::test]
+    async fn events_from_all_shards_are_merged_and_order_updates_deduplicated() {
+        let (pool_tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let dedup = Arc::new(Mutex::new(OrderUpdateDedup::default()));
+        // Subscribed before any shard exists, as `KiteTickerPool::events` allows.
+        let mut merged = pool_tx.subscribe();
+
+        let mut shards = Vec::new();
+        for idx in 0..3 {
+            let (tx, rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+            tokio::spawn(forward_events(idx, rx, pool_tx.clone(), dedup.clone()));
+            shards.push(tx);
+        }
+        let fill = order_update("240610000000001", "COMPLETE", Some("2024-06-10 09:15:03"));
+        for (idx, tx) in shards.iter().enumerate() {
+            let message = TickerEvent::Message(format!("shard {idx}"));
+            tx.send(TickerEvent::OrderUpdate(Box::new(fill.clone())))
+                .unwrap();
+            tx.send(message).unwrap();
+        }
+
+        let mut orders = 0;
+        let mut messages = Vec::new();
+        for _ in 0..4 {
+            let event = tokio::time::timeout(Duration::from_secs(1), merged.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            match event {
+                TickerEvent::OrderUpdate(o) => {
+                    assert_eq!(o.order_id, fill.order_id);
+                    orders += 1;
+                }
+                TickerEvent::Message(m) => messages.push(m),
+                TickerEvent::Error(e) => panic!("unexpected error event {e}"),
+            }
+        }
+        messages.sort();
+        assert_eq!(orders, 1);
+        assert_eq!(messages, ["shard 0", "shard 1", "shard 2"]);
+        assert!(merged.try_recv().is_err());
+    }
+
+    fn tokens(range: std::ops::Range<i32>) -> Vec<i32> {
+        range.collect()
+    }
+
+    /// Every token sits on exactly one shard within the per-connection limit,
+    /// and `assignments` agrees with the shards.
+    fn assert_consistent(p: &Placement, config: &TickerPoolConfig) -> Vec<usize> {
+        assert!(p.shards.len() <= config.max_connections);
+        let loads: Vec<usize> = p.shards.iter().map(HashMap::len).collect();
+        assert_eq!(loads.iter().sum::<usize>(), p.assignments.len());
+        for (t, idx) in &p.assignments {
+            assert!(
+                p.shards[*idx].contains_key(t),
+                "token {t} missing on shard {idx}"
+            );
+        }
+        assert!(loads.iter().all(|l| *l <= config.max_tokens_per_connection));
+        loads
+    }
+
+    #[test]
+    fn placement_fills_kite_limits_exactly() {
+        let config = TickerPoolConfig::default();
+        let mut p = Placement::default();
+
+        let ops = p
+            .subscribe(&tokens(0..3000), TickMode::Full, &config)
+            .unwrap();
+        assert_eq!(assert_consistent(&p, &config), vec![3000]);
+        assert_eq!(ops.len(), 1);
+
+        p.subscribe(&tokens(3000..9000), TickMode::Quote, &config)
+            .unwrap();
+        assert_eq!(assert_consistent(&p, &config), vec![3000, 3000, 3000]);
+
+        let err = p.subscribe(&[9000], TickMode::Ltp, &config).unwrap_err();
+        assert!(err.to_string().contains("capacity exceeded"), "{err}");
+        assert_eq!(assert_consistent(&p, &config), vec![3000, 3000, 3000]);
+        assert!(!p.assignments.contains_key(&9000));
+
+        // Known tokens only change mode, even at capacity.
+        let ops = p.subscribe(&[0, 1], TickMode::Ltp, &config).unwrap();
+        assert_eq!(
+            ops,
+            vec![ShardOp::Subscribe {
+                shard: 0,
+                tokens: vec![0, 1],
+                mode: TickMode::Ltp,
+            }]
+        );
+        assert_eq!(p.shards[0][&0], TickMode::Ltp);
+    }
+
+    #[test]
+    fn placement_opens_a_shard_past_3000_and_rebalances_onto_it() {
+        let config = TickerPoolConfig::default();
+        let mut p = Placement::default();
+        p.subscribe(&tokens(0..3000), TickMode::Full, &config)
+            .unwrap();
+
+        let ops = p.subscribe(&[3000], TickMode::Full, &config).unwrap();
+        let loads = assert_consistent(&p, &config);
+        assert_eq!(loads.len(), 2);
+        assert!(loads[0].abs_diff(loads[1]) <= config.rebalance_threshold);
+
+        // The new token first, then moved tokens join shard 1 before leaving shard 0.
+        assert_eq!(
+            ops[0],
+            ShardOp::Subscribe {
+                shard: 1,
+                tokens: vec![3000],
+                mode: TickMode::Full,
+            }
+        );
+        let joined = ops
+            .iter()
+            .position(
+                |op| matches!(op, ShardOp::Subscribe { shard: 1, tokens, .. } if tokens.len() > 1),
+            )
+            .unwrap();
+        let left = ops
+            .iter()
+            .position(|op| matches!(op, ShardOp::Unsubscribe { shard: 0, .. }))
+            .unwrap();
+        assert!(joined < left);
+    }
+
+    #[test]
+    fn placement_rebalances_after_unsubscribe_and_keeps_shards() {
+        let config = TickerPoolConfig::default();
+        let mut p = Placement::default();
+        p.subscribe(&tokens(0..9000), TickMode::Full, &config)
+            .unwrap();
+        let on_first: Vec<i32> = p.shards[0].keys().copied().take(2000).collect();
+
+        let ops = p.unsubscribe(&on_first, &config);
+        let loads = assert_consistent(&p, &config);
+        assert_eq!(loads.len(), 3);
+        assert_eq!(loads.iter().sum::<usize>(), 7000);
+        let (min, max) = (loads.iter().min().unwrap(), loads.iter().max().unwrap());
+        assert!(max - min <= config.rebalance_threshold, "{loads:?}");
+        assert!(
+            matches!(&ops[0], ShardOp::Unsubscribe { shard: 0, tokens } if tokens.len() == 2000)
+        );
+
+        // Unknown tokens are ignored; emptying the pool keeps its shards.
+        assert!(p.unsubscribe(&[-1], &config).is_empty());
+        p.unsubscribe(&tokens(0..9000), &config);
+        assert_eq!(assert_consistent(&p, &config), vec![0, 0, 0]);
+
+        // Room again for a full connection's worth.
+        p.subscribe(&tokens(0..3000), TickMode::Ltp, &config)
+            .unwrap();
+        assert_eq!(assert_consistent(&p, &config), vec![1000, 1000, 1000]);
+    }
+
+    #[test]
+    fn placement_set_mode_ignores_unknown_tokens() {
+        let config = TickerPoolConfig::default();
+        let mut p = Placement::default();
+        p.subscribe(&[1, 2], TickMode::Full, &config).unwrap();
+        let ops = p.set_mode(&[2, 99], TickMode::Ltp);
+        assert_eq!(
+            ops,
+            vec![ShardOp::SetMode {
+                shard: 0,
+                tokens: vec![2],
+                mode: TickMode::Ltp,
+            }]
+        );
+        assert_eq!(p.shards[0][&1], TickMode::Full);
+        assert_eq!(p.shards[0][&2], TickMode::Ltp);
+    }
+}
@@ -0,0 +1,13 @@
+//! Zerodha Kite Connect client, tick store and API server.
+//!
+//! Shared by the `zatamap-trade-rust` CLI/server and the `kite-sim` simulator.
+
+pub mod api;
+pub mod auth;
+pub mod bootstrap;
+pub mod core;
+pub mod dao;
+pub mod db;
+pub mod instruments;
+pub mod kite;
+pub mod ticks;
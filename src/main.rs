@@ -1,27 +1,34 @@
-mod api;
-mod auth;
-mod bootstrap;
-mod core;
-mod dao;
-mod db;
-mod instruments;
-mod kite;
-mod ticks;
-
-use crate::core::AppError;
-use crate::kite::client::KiteClient;
-use crate::kite::ws::{KiteTickerWs, TickLogConfig};
-use crate::ticks::{TickStore, TokenMeta};
-use crate::{core::AppConfig, core::AppState, db::Db};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
+use zatamap_trade_rust::core::AppError;
+use zatamap_trade_rust::dao::profile_dao::UserKiteCreds;
+use zatamap_trade_rust::kite::client::KiteClient;
+use zatamap_trade_rust::kite::types::{ConvertPositionParams, GttLeg, GttParams};
+use zatamap_trade_rust::kite::ws::{TickLogConfig, TokenRefresher};
+use zatamap_trade_rust::kite::ws_pool::{KiteTickerPool, TickerPoolConfig};
+use zatamap_trade_rust::ticks::history::TickHistoryConfig;
+use zatamap_trade_rust::ticks::{TickMode, TickStore, TokenMeta};
+use zatamap_trade_rust::{api, auth, bootstrap, dao, kite};
+use zatamap_trade_rust::{core::AppConfig, core::AppState, db::Db};
 
 fn usage() -> &'static str {
-        r#"Usage:
+    r#"Usage:
     cargo run -- server
     cargo run -- profile
     cargo run -- holdings
+    cargo run -- orders [ORDER_ID]
+    cargo run -- trades [ORDER_ID]
+    cargo run -- positions
+    cargo run -- positions convert <EXCHANGE> <TRADINGSYMBOL> <BUY|SELL> <day|overnight> <QTY> <OLD_PRODUCT> <NEW_PRODUCT>
+    cargo run -- gtt list
+    cargo run -- gtt get <TRIGGER_ID>
+    cargo run -- gtt delete <TRIGGER_ID>
+    cargo run -- gtt create single <EXCHANGE> <TRADINGSYMBOL> <LAST_PRICE> <BUY|SELL> <QTY> <PRODUCT> <TRIGGER> <PRICE>
+    cargo run -- gtt create oco <EXCHANGE> <TRADINGSYMBOL> <LAST_PRICE> <BUY|SELL> <QTY> <PRODUCT> <SL_TRIGGER> <SL_PRICE> <TARGET_TRIGGER> <TARGET_PRICE>
+    cargo run -- gtt modify <TRIGGER_ID> single|oco <same args as create>
     cargo run -- autologin <USER_ID> [--debug] [--force]
+    cargo run -- logout <USER_ID>
     cargo run -- e2e <USER_ID> [--debug] [--force] [--no-force] [--print-ticks] [--no-print-ticks]
     cargo run -- ticker <USER_ID> [--print-ticks] [--no-print-ticks]
 
@@ -29,11 +36,21 @@ Env (CLI):
     KITE_API_KEY
     KITE_ACCESS_TOKEN
 
+Kite REST retries (GET requests only; orders are never retried):
+    KITE_HTTP_MAX_RETRIES (default 3; 0 disables)
+    KITE_HTTP_RETRY_BASE_MS (default 250)
+    KITE_HTTP_RETRY_MAX_MS (default 10000)
+
 Env (server/autologin):
     SERVER_ADDR (default 127.0.0.1:8080)
     DATABASE_URL  (or PGHOST/PGPORT/PGDATABASE/PGUSER/PGPASSWORD/PGSSLMODE)
     KITE_CALLBACK_URL
 
+Kite hosts (point at a local simulator for CI / dry runs):
+    KITE_API_BASE_URL (default https://api.kite.trade)
+    KITE_WS_URL (default wss://ws.kite.trade)
+    KITE_LOGIN_URL (default https://kite.zerodha.com/connect/login)
+
 Optional:
     AUTOLOGIN_USER_ID (legacy alias for STARTUP_AUTOLOGIN_USER_ID)
     STARTUP_AUTOLOGIN_USER_ID (runs autologin during server startup)
@@ -46,6 +63,23 @@ Optional:
     CHROMEDRIVER_PATH (override chromedriver binary to spawn)
     CHROME_BINARY_PATH (override Chrome binary path)
 
+Ticker connections (tokens are sharded over several websockets):
+    KITE_WS_MAX_TOKENS_PER_CONN (default 3000)
+    KITE_WS_MAX_CONNECTIONS (default 3)
+    KITE_WS_REBALANCE_THRESHOLD (default 300; token gap between shards that triggers a rebalance)
+    KITE_WS_READ_TIMEOUT_MS (default 10000; reconnect a shard that receives no frame/heartbeat for this long;
+                             also bounds the connect + handshake)
+    TICKER_HTTP_ADDR (if set, e.g. 127.0.0.1:8081, serve only /api/health with feed status)
+    TICKER_HEALTH_FILE (if set, e.g. /tmp/zatamap-feed-health.json, the ticker writes feed health there
+                        every 2s and the server's /api/health reports it; stale after 10s)
+
+Ticker token expiry:
+    TICKER_AUTOLOGIN (default 0; 1/true runs a forced autologin when the ticker's access_token is rejected)
+
+Ticker tick history (per-token ring buffer of recent ticks):
+    TICK_HISTORY_CAPACITY (default 300; 0 disables history)
+    TICK_HISTORY_CAPACITY_<INSTRUMENT_TYPE> (per-type override, e.g. TICK_HISTORY_CAPACITY_FUT=600; defaults CE=60, PE=60)
+
 Ticker logging:
     TICK_LOG_FULL (default 1/on; set to 0/off to disable)
     TICK_LOG_INTERVAL_MS (default 500; rate-limit tick logs)
@@ -84,6 +118,19 @@ async fn main() -> Result<(), AppError> {
 
             run_autologin(&user_id, debug, force).await?;
         }
+        "logout" => {
+            let user_id = args.next().unwrap_or_default();
+            if user_id.is_empty() {
+                eprintln!("Missing USER_ID\n\n{}", usage());
+                std::process::exit(2);
+            }
+            if let Some(a) = args.next() {
+                eprintln!("Unknown flag for logout: {a}\n\n{}", usage());
+                std::process::exit(2);
+            }
+
+            run_logout(&user_id).await?;
+        }
         "e2e" => {
             let user_id = args.next().unwrap_or_default();
             if user_id.is_empty() {
@@ -130,21 +177,68 @@ async fn main() -> Result<(), AppError> {
             }
             run_ticker(&user_id, tick_log_enabled_override).await?;
         }
-        "profile" | "holdings" => {
-            let api_key =
-                std::env::var("KITE_API_KEY").map_err(|_| AppError::MissingEnv("KITE_API_KEY"))?;
-            let access_token = std::env::var("KITE_ACCESS_TOKEN")
-                .map_err(|_| AppError::MissingEnv("KITE_ACCESS_TOKEN"))?;
-
-            let kite = KiteClient::new(&api_key, &access_token)?;
-            if cmd == "profile" {
-                let profile = kite.profile().await?;
-                println!("{}", serde_json::to_string_pretty(&profile)?);
-            } else {
-                let holdings = kite.holdings().await?;
-                println!("{}", serde_json::to_string_pretty(&holdings)?);
+        "profile" | "holdings" | "orders" | "trades" => {
+            let kite = kite_client_from_env()?;
+            // Optional ORDER_ID narrows `orders`/`trades` down to one order.
+            let order_id = args.next();
+            match (cmd.as_str(), order_id) {
+                ("profile", _) => {
+                    let profile = kite.profile().await?;
+                    println!("{}", serde_json::to_string_pretty(&profile)?);
+                }
+                ("holdings", _) => {
+                    let holdings = kite.holdings().await?;
+                    println!("{}", serde_json::to_string_pretty(&holdings)?);
+                }
+                ("orders", Some(id)) => {
+                    let history = kite.order_history(&id).await?;
+                    println!("{}", serde_json::to_string_pretty(&history)?);
+                }
+                ("orders", None) => {
+                    let orders = kite.orders().await?;
+                    println!("{}", serde_json::to_string_pretty(&orders)?);
+                }
+                ("trades", Some(id)) => {
+                    let trades = kite.order_trades(&id).await?;
+                    println!("{}", serde_json::to_string_pretty(&trades)?);
+                }
+                _ => {
+                    let trades = kite.trades().await?;
+                    println!("{}", serde_json::to_string_pretty(&trades)?);
+                }
+            }
+        }
+        "positions" => {
+            let kite = kite_client_from_env()?;
+
+            match args.next().as_deref() {
+                None => {
+                    let positions = kite.positions().await?;
+                    println!("{}", serde_json::to_string_pretty(&positions)?);
+                }
+                Some("convert") => {
+                    let rest: Vec<String> = args.collect();
+                    let params = match parse_convert_position_args(&rest) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("{e}\n\n{}", usage());
+                            std::process::exit(2);
+                        }
+                    };
+                    let ok = kite.convert_position(&params).await?;
+                    println!("{}", serde_json::json!({"converted": ok}));
+                }
+                Some(other) => {
+                    eprintln!("Unknown positions subcommand: {other}\n\n{}", usage());
+                    std::process::exit(2);
+                }
             }
         }
+        "gtt" => {
+            let kite = kite_client_from_env()?;
+            let rest: Vec<String> = args.collect();
+            run_gtt_cli(&kite, &rest).await?;
+        }
         _ => {
             eprintln!("Unknown command: {}\n\n{}", cmd, usage());
             std::process::exit(2);
@@ -154,6 +248,136 @@ async fn main() -> Result<(), AppError> {
     Ok(())
 }
 
+/// Kite REST client for the DB-less CLI commands (KITE_API_KEY + KITE_ACCESS_TOKEN).
+fn kite_client_from_env() -> Result<KiteClient, AppError> {
+    let api_key =
+        std::env::var("KITE_API_KEY").map_err(|_| AppError::MissingEnv("KITE_API_KEY"))?;
+    let access_token = std::env::var("KITE_ACCESS_TOKEN")
+        .map_err(|_| AppError::MissingEnv("KITE_ACCESS_TOKEN"))?;
+    KiteClient::new(&api_key, &access_token)
+}
+
+async fn run_gtt_cli(kite: &KiteClient, args: &[String]) -> Result<(), AppError> {
+    let sub = args.first().map(|s| s.as_str()).unwrap_or("list");
+    let parsed: Result<(), String> = match (sub, args.get(1..).unwrap_or_default()) {
+        ("list", []) => {
+            let gtts = kite.gtts().await?;
+            println!("{}", serde_json::to_string_pretty(&gtts)?);
+            Ok(())
+        }
+        ("get", [id]) => match id.parse::<i64>() {
+            Ok(id) => {
+                let gtt = kite.gtt(id).await?;
+                println!("{}", serde_json::to_string_pretty(&gtt)?);
+                Ok(())
+            }
+            Err(e) => Err(format!("invalid TRIGGER_ID {id}: {e}")),
+        },
+        ("delete", [id]) => match id.parse::<i64>() {
+            Ok(id) => {
+                let resp = kite.delete_gtt(id).await?;
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                Ok(())
+            }
+            Err(e) => Err(format!("invalid TRIGGER_ID {id}: {e}")),
+        },
+        ("create", rest) => match parse_gtt_params(rest) {
+            Ok(params) => {
+                let resp = kite.create_gtt(&params).await?;
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        },
+        ("modify", [id, rest @ ..]) => match (id.parse::<i64>(), parse_gtt_params(rest)) {
+            (Ok(id), Ok(params)) => {
+                let resp = kite.modify_gtt(id, &params).await?;
+                println!("{}", serde_json::to_string_pretty(&resp)?);
+                Ok(())
+            }
+            (Err(e), _) => Err(format!("invalid TRIGGER_ID {id}: {e}")),
+            (_, Err(e)) => Err(e),
+        },
+        _ => Err(format!("Unknown gtt arguments: {}", args.join(" "))),
+    };
+
+    if let Err(e) = parsed {
+        eprintln!("{e}\n\n{}", usage());
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+/// `single <EXCHANGE> <TRADINGSYMBOL> <LAST_PRICE> <BUY|SELL> <QTY> <PRODUCT> <TRIGGER> <PRICE>`
+/// or
+/// `oco <EXCHANGE> <TRADINGSYMBOL> <LAST_PRICE> <BUY|SELL> <QTY> <PRODUCT> <SL_TRIGGER> <SL_PRICE> <TARGET_TRIGGER> <TARGET_PRICE>`
+fn parse_gtt_params(args: &[String]) -> Result<GttParams, String> {
+    fn num<T: std::str::FromStr>(name: &str, v: &str) -> Result<T, String>
+    where
+        T::Err: std::fmt::Display,
+    {
+        v.parse().map_err(|e| format!("invalid {name} {v}: {e}"))
+    }
+
+    match args {
+        [kind, exchange, tradingsymbol, last_price, transaction_type, quantity, product, legs @ ..] =>
+        {
+            let last_price: f64 = num("LAST_PRICE", last_price)?;
+            let leg = |price: &str| -> Result<GttLeg, String> {
+                Ok(GttLeg {
+                    transaction_type: transaction_type.parse()?,
+                    quantity: num("QTY", quantity)?,
+                    product: product.parse()?,
+                    price: num("PRICE", price)?,
+                })
+            };
+            match (kind.as_str(), legs) {
+                ("single", [trigger, price]) => Ok(GttParams::single(
+                    exchange.as_str(),
+                    tradingsymbol.as_str(),
+                    last_price,
+                    num("TRIGGER", trigger)?,
+                    leg(price)?,
+                )),
+                ("oco", [sl_trigger, sl_price, target_trigger, target_price]) => GttParams::oco(
+                    exchange.as_str(),
+                    tradingsymbol.as_str(),
+                    last_price,
+                    num("SL_TRIGGER", sl_trigger)?,
+                    leg(sl_price)?,
+                    num("TARGET_TRIGGER", target_trigger)?,
+                    leg(target_price)?,
+                )
+                .map_err(|e| e.message().to_string()),
+                _ => Err(format!("invalid gtt {kind} arguments: {}", args.join(" "))),
+            }
+        }
+        _ => Err(format!("not enough gtt arguments: {}", args.join(" "))),
+    }
+}
+
+fn parse_convert_position_args(args: &[String]) -> Result<ConvertPositionParams, String> {
+    let [exchange, tradingsymbol, transaction_type, position_type, quantity, old_product, new_product] =
+        args
+    else {
+        return Err(format!(
+            "positions convert expects 7 arguments, got {}",
+            args.len()
+        ));
+    };
+    Ok(ConvertPositionParams {
+        exchange: exchange.to_string(),
+        tradingsymbol: tradingsymbol.to_string(),
+        transaction_type: transaction_type.parse()?,
+        position_type: position_type.parse()?,
+        quantity: quantity
+            .parse()
+            .map_err(|e| format!("invalid quantity {quantity}: {e}"))?,
+        old_product: old_product.parse()?,
+        new_product: new_product.parse()?,
+    })
+}
+
 async fn run_server() -> Result<(), AppError> {
     let config = AppConfig::from_env()?;
     let db = Db::connect(&config.database_url).await?;
@@ -167,6 +391,7 @@ async fn run_server() -> Result<(), AppError> {
         config: Arc::new(config),
         db: Arc::new(db),
         ticks: Arc::new(TickStore::default()),
+        ticker: None,
     };
 
     bootstrap::initialize_on_startup(&state).await?;
@@ -222,6 +447,7 @@ async fn run_autologin(user_id: &str, debug: bool, force: bool) -> Result<(), Ap
         config: Arc::new(config),
         db: Arc::new(db),
         ticks: Arc::new(TickStore::default()),
+        ticker: None,
     };
 
     auth::autologin::maybe_autologin(
@@ -232,6 +458,57 @@ async fn run_autologin(user_id: &str, debug: bool, force: bool) -> Result<(), Ap
     .await
 }
 
+/// Revoke the user's Kite session and clear the stored tokens.
+async fn run_logout(user_id: &str) -> Result<(), AppError> {
+    let config = AppConfig::from_env_ticker()?;
+    let db = Db::connect(&config.database_url).await?;
+    let os_type = config.os_type.clone();
+
+    let creds = dao::profile_dao::get_user_kite_creds_for_os(&db, user_id, &os_type)
+        .await?
+        .ok_or_else(|| {
+            AppError::KiteApi(format!(
+                "user not found in trade.profile: {user_id} (os_type={os_type})"
+            ))
+        })?;
+
+    match creds
+        .access_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(access_token) => {
+            match kite::auth::invalidate_access_token(
+                &config.kite.api_base_url,
+                &creds.api_key,
+                access_token,
+            )
+            .await
+            {
+                Ok(ok) => info!(
+                    user_id = user_id,
+                    invalidated = ok,
+                    "kite session invalidated"
+                ),
+                // Kite no longer accepts the token, so there is nothing left to revoke.
+                Err(e) if e.is_kite_token_error() => {
+                    info!(user_id = user_id, error = %e, "kite session already invalid")
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        None => info!(
+            user_id = user_id,
+            "no access_token stored; skipping kite logout"
+        ),
+    }
+
+    let n = dao::profile_dao::clear_session_tokens_for_os(&db, user_id, &os_type).await?;
+    info!(user_id = user_id, os_type = %os_type, cleared_rows = n, "session tokens cleared in DB");
+    Ok(())
+}
+
 async fn run_e2e(
     user_id: &str,
     debug: bool,
@@ -242,47 +519,146 @@ async fn run_e2e(
     run_ticker(user_id, tick_log_enabled_override).await
 }
 
-async fn run_ticker(user_id: &str, tick_log_enabled_override: Option<bool>) -> Result<(), AppError> {
+/// Kite creds for the ticker user: the `OS_TYPE` row, else the most recent row.
+async fn load_ticker_creds(
+    state: &AppState,
+    user_id: &str,
+) -> Result<Option<UserKiteCreds>, AppError> {
+    match dao::profile_dao::get_user_kite_creds_for_os(&state.db, user_id, &state.config.os_type)
+        .await?
+    {
+        Some(c) => Ok(Some(c)),
+        None => dao::profile_dao::get_user_kite_creds(&state.db, user_id).await,
+    }
+}
+
+async fn stored_access_token(state: &AppState, user_id: &str) -> Result<Option<String>, AppError> {
+    Ok(load_ticker_creds(state, user_id)
+        .await?
+        .and_then(|c| c.access_token)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty()))
+}
+
+/// Minimum gap between ticker-triggered autologins, so a broken login flow
+/// does not hit Zerodha on every reconnect.
+const TICKER_AUTOLOGIN_MIN_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Token refresher for the ticker.
+///
+/// Re-reads `trade.profile` first, since the callback flow or a scheduled
+/// autologin may already have stored a new token. If the stored token is
+/// still the rejected one and `autologin` is set, runs a forced autologin and
+/// reads again.
+fn ticker_token_refresher(state: AppState, user_id: String, autologin: bool) -> TokenRefresher {
+    let last_autologin: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    Arc::new(move |rejected: String| {
+        let state = state.clone();
+        let user_id = user_id.clone();
+        let last_autologin = last_autologin.clone();
+        Box::pin(async move {
+            let stored = stored_access_token(&state, &user_id).await?;
+            if stored.as_deref().is_some_and(|t| t != rejected) {
+                info!(user_id = %user_id, "found newer access_token in DB");
+                return Ok(stored);
+            }
+            if !autologin {
+                warn!(user_id = %user_id, "access_token expired; waiting for a new one in DB (set TICKER_AUTOLOGIN=1 to log in automatically)");
+                return Ok(None);
+            }
+            {
+                let mut last = last_autologin.lock().unwrap_or_else(|e| e.into_inner());
+                if last.is_some_and(|t| t.elapsed() < TICKER_AUTOLOGIN_MIN_INTERVAL) {
+                    info!(user_id = %user_id, "ticker autologin ran recently; not retrying yet");
+                    return Ok(None);
+                }
+                *last = Some(Instant::now());
+            }
+            info!(user_id = %user_id, "access_token rejected by ticker; running autologin");
+            auth::autologin::maybe_autologin_for_os(
+                &state,
+                &user_id,
+                &state.config.os_type,
+                auth::autologin::AutoLoginOptions {
+                    debug: false,
+                    force: true,
+                },
+            )
+            .await?;
+            stored_access_token(&state, &user_id).await
+        })
+    })
+}
+
+async fn run_ticker(
+    user_id: &str,
+    tick_log_enabled_override: Option<bool>,
+) -> Result<(), AppError> {
     let config = AppConfig::from_env_ticker()?;
     let db = Db::connect(&config.database_url).await?;
     let state = AppState {
         config: Arc::new(config),
         db: Arc::new(db),
-        ticks: Arc::new(TickStore::default()),
+        ticks: Arc::new(TickStore::default().with_history(TickHistoryConfig::from_env())),
+        ticker: None,
     };
 
     let os_type = state.config.os_type.clone();
 
-    let creds = match dao::profile_dao::get_user_kite_creds_for_os(&state.db, user_id, &os_type)
-        .await?
-    {
-        Some(c) => Some(c),
-        None => dao::profile_dao::get_user_kite_creds(&state.db, user_id).await?,
-    };
-    let creds = creds.ok_or_else(|| AppError::KiteApi(format!("user not found in trade.profile: {user_id}")))?;
+    let creds = load_ticker_creds(&state, user_id).await?;
+    let creds = creds
+        .ok_or_else(|| AppError::KiteApi(format!("user not found in trade.profile: {user_id}")))?;
     let access_token = creds
         .access_token
         .clone()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
-        .ok_or_else(|| AppError::KiteApi(format!("no access_token for user_id={user_id} (run autologin first)")))?;
+        .ok_or_else(|| {
+            AppError::KiteApi(format!(
+                "no access_token for user_id={user_id} (run autologin first)"
+            ))
+        })?;
 
     let at_len = access_token.len();
-    let at_tail = access_token.chars().rev().take(4).collect::<String>().chars().rev().collect::<String>();
+    let at_tail = access_token
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<String>()
+        .chars()
+        .rev()
+        .collect::<String>();
     info!(user_id = user_id, access_token_len = at_len, access_token_tail4 = %at_tail, "loaded access_token from DB");
 
+    let autologin_on_expiry = std::env::var("TICKER_AUTOLOGIN")
+        .map(|v| {
+            matches!(
+                v.trim(),
+                "1" | "true" | "TRUE" | "yes" | "YES" | "on" | "ON"
+            )
+        })
+        .unwrap_or(false);
+    info!(
+        autologin_on_expiry = autologin_on_expiry,
+        "ticker token-expiry recovery enabled"
+    );
+    let refresher = ticker_token_refresher(state.clone(), user_id.to_string(), autologin_on_expiry);
+
     // Preflight: verify token works for REST. If this fails, WS will also fail.
-    let kite = KiteClient::new(&creds.api_key, &access_token)?;
-    match kite.profile().await {
-        Ok(_) => info!(user_id = user_id, "kite REST auth preflight OK"),
-        Err(e) => {
-            warn!(user_id = user_id, error = %e, "kite REST auth preflight failed (token likely expired; run autologin)");
-        }
-    }
+    // An expired token (e.g. after the 6 AM reset) is refreshed here the same
+    // way the ticker refreshes it later.
+    let (kite, access_token) = kite::auth::verify_session(
+        &state.config.kite.api_base_url,
+        &creds.api_key,
+        access_token,
+        Some(&refresher),
+    )
+    .await?;
 
     // Select NIFTY current-week option tokens from DB.
     // This mirrors the Python flow which only subscribes to the nearest weekly expiry.
-    let (_expiry, rows) = dao::instrument_dao::fetch_nifty_current_week_option_meta(&state.db, 7).await?;
+    let (_expiry, rows) =
+        dao::instrument_dao::fetch_nifty_current_week_option_meta(&state.db, 7).await?;
     let mut metas: Vec<TokenMeta> = rows
         .into_iter()
         .map(|r| {
@@ -320,6 +696,20 @@ async fn run_ticker(user_id: &str, tick_log_enabled_override: Option<bool>) -> R
     tokens.sort_unstable();
     tokens.dedup();
 
+    // Seed last ticks from a REST quote snapshot so readers have data before the
+    // first websocket frame. Best-effort: the websocket fills in regardless.
+    let instruments: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+    match kite.quote(&instruments).await {
+        Ok(quotes) => {
+            let n = quotes.len();
+            for (_, tick) in quotes {
+                state.ticks.seed_tick(tick);
+            }
+            info!(seeded = n, "tick store seeded from REST quotes");
+        }
+        Err(e) => warn!(error = %e, "REST quote snapshot failed; waiting for websocket ticks"),
+    }
+
     info!(user_id = user_id, os_type = %os_type, tokens = tokens.len(), "starting kite ticker ws");
 
     let mut log = TickLogConfig::from_env();
@@ -334,20 +724,98 @@ async fn run_ticker(user_id: &str, tick_log_enabled_override: Option<bool>) -> R
         tick_log_overridden = has_override,
         "ticker tick-log config"
     );
-    let ws = KiteTickerWs::new(creds.api_key, access_token, tokens, state.ticks.clone(), log);
-    let handle = ws.spawn();
+    let pool_config = TickerPoolConfig::from_env();
+    info!(
+        max_tokens_per_connection = pool_config.max_tokens_per_connection,
+        max_connections = pool_config.max_connections,
+        "ticker pool config"
+    );
+    let pool = Arc::new(
+        KiteTickerPool::new(
+            state.config.kite.ws_url.clone(),
+            creds.api_key,
+            access_token,
+            state.ticks.clone(),
+            log,
+            pool_config,
+        )
+        .with_token_refresher(refresher),
+    );
+    pool.subscribe(&tokens, TickMode::Full)?;
+
+    // Optional: serve /api/health (with feed status) from the ticker process. Only
+    // the health route: the login callback and account endpoints stay on the server.
+    if let Some(addr) = std::env::var("TICKER_HTTP_ADDR")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+    {
+        let addr: std::net::SocketAddr = addr
+            .trim()
+            .parse()
+            .map_err(|e| AppError::KiteApi(format!("Invalid TICKER_HTTP_ADDR: {e}")))?;
+        let app = api::health_router(AppState {
+            ticker: Some(pool.clone()),
+            ..state.clone()
+        });
+        info!(addr = %addr, "ticker http listening");
+        tokio::spawn(async move {
+            if let Err(e) = axum::Server::bind(&addr)
+                .serve(app.into_make_service())
+                .await
+            {
+                warn!(error = %e, "ticker http server exited");
+            }
+        });
+    }
 
     // Periodic health logs (does not log individual ticks to avoid flooding).
     let store = state.ticks.clone();
+    let stats_pool = pool.clone();
+    let health_file = state.config.ticker_health_file.clone();
+    if let Some(path) = &health_file {
+        info!(path = %path.display(), "ticker writing feed health snapshots");
+    }
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+        let mut prev = stats_pool.stats();
         loop {
             interval.tick().await;
+            let feed = stats_pool.stats();
+            let rates = feed.rates_since(&prev);
+            prev = feed;
+            let bus = store.bus().stats();
+            let shards = stats_pool.health();
+            let shards_connected = shards.iter().filter(|s| s.health.connected).count();
             info!(
                 subscribed_tokens = store.len(),
                 received_tokens = store.received_token_count(),
+                bus_subscribers = bus.subscribers,
+                bus_dropped = bus.dropped,
+                shards = shards.len(),
+                shards_connected = shards_connected,
+                feed_alive = !shards.is_empty() && shards.iter().all(|s| s.health.alive),
+                frames_per_s = format!("{:.1}", rates.frames_per_s),
+                bytes_per_s = format!("{:.0}", rates.bytes_per_s),
+                ticks_per_s = format!("{:.1}", rates.ticks_per_s),
+                packets_per_frame = format!("{:.2}", feed.packets_per_frame()),
+                unknown_packets = feed.unknown_packets,
+                filtered_ticks = feed.filtered_ticks,
+                reconnects = feed.reconnects,
                 "ticker stats"
             );
+            if let Some(path) = &health_file {
+                if let Err(e) = stats_pool.feed_health().write_snapshot(path) {
+                    warn!(error = %e, "feed health snapshot failed");
+                }
+            }
+            for s in shards.iter().filter(|s| !s.health.connected) {
+                warn!(
+                    shard = s.shard,
+                    subscribed_tokens = s.health.subscribed_tokens,
+                    last_error = s.health.last_error.as_deref().unwrap_or(""),
+                    "ticker shard disconnected"
+                );
+            }
         }
     });
 
@@ -369,6 +837,6 @@ async fn run_ticker(user_id: &str, tick_log_enabled_override: Option<bool>) -> R
             info!("ctrl-c received; stopping");
         }
     }
-    handle.abort();
+    pool.shutdown();
     Ok(())
 }
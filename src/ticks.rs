@@ -1,4 +1,13 @@
+pub mod bus;
+pub mod encode;
+pub mod history;
+pub mod packet;
+
+pub use packet::{visit_binary_ticks, PacketLayout, TickPacket};
+
+use bus::{TickBus, TickFilter, TickSubscription, DEFAULT_SUBSCRIBER_CAPACITY};
 use dashmap::DashMap;
+use history::{TickHistory, TickHistoryConfig, TickSummary};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -10,6 +19,30 @@ pub enum TickMode {
     Full,
 }
 
+impl TickMode {
+    /// Mode name as used in ticker `{"a":"mode"}` messages.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TickMode::Ltp => "ltp",
+            TickMode::Quote => "quote",
+            TickMode::Full => "full",
+        }
+    }
+}
+
+impl std::str::FromStr for TickMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ltp" => Ok(TickMode::Ltp),
+            "quote" => Ok(TickMode::Quote),
+            "full" => Ok(TickMode::Full),
+            other => Err(format!("invalid tick mode: {other}")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Ohlc {
     pub open: f64,
@@ -154,16 +187,107 @@ impl TokenState {
     }
 }
 
+/// The previous tick's values that the rate-of-change metrics need.
+#[derive(Debug, Clone, Copy)]
+struct RocBase {
+    received_ns: u64,
+    last_price: f64,
+    open_interest: Option<u32>,
+    volume_traded: Option<u32>,
+}
+
+impl RocBase {
+    fn of(tick: &Tick) -> Self {
+        Self {
+            received_ns: tick.received_ns,
+            last_price: tick.last_price,
+            open_interest: tick.open_interest,
+            volume_traded: tick.volume_traded,
+        }
+    }
+}
+
+/// Update spread and ROC metrics for `tick`, given the tick before it.
+fn update_derived(derived: &mut DerivedMetrics, prev: Option<RocBase>, tick: &Tick) {
+    if let Some(prev) = prev {
+        let dt_ns = tick.received_ns.saturating_sub(prev.received_ns);
+        let dt_s = (dt_ns as f64) / 1_000_000_000.0;
+        if dt_s > 0.0 {
+            derived.price_roc_per_s = Some((tick.last_price - prev.last_price) / dt_s);
+            if let (Some(oi), Some(poi)) = (tick.open_interest, prev.open_interest) {
+                derived.oi_roc_per_s = Some(((oi as f64) - (poi as f64)) / dt_s);
+            }
+            if let (Some(v), Some(pv)) = (tick.volume_traded, prev.volume_traded) {
+                derived.vol_roc_per_s = Some(((v as f64) - (pv as f64)) / dt_s);
+            }
+        }
+    }
+
+    // Spread from depth (FULL mode).
+    if let Some(depth) = tick.depth.as_ref() {
+        let bid = depth.buy[0].price;
+        let ask = depth.sell[0].price;
+        derived.best_bid = Some(bid);
+        derived.best_ask = Some(ask);
+        let spread = ask - bid;
+        derived.spread = Some(spread);
+        derived.spread_bps = if tick.last_price > 0.0 {
+            Some((spread / tick.last_price) * 10_000.0)
+        } else {
+            None
+        };
+    }
+}
+
+/// Store entry: the state readers clone, plus the token's rolling history
+/// (kept apart so `get_state` does not copy it).
+#[derive(Debug)]
+struct TokenEntry {
+    state: TokenState,
+    history: TickHistory,
+}
+
 /// Shared in-memory store for the latest tick per token.
 ///
 /// This is designed to be read frequently by other modules (signals/strategy)
-/// while a single websocket task keeps updating it.
-#[derive(Debug, Default)]
+/// while a single websocket task keeps updating it. Consumers that need every
+/// tick rather than the latest one can `subscribe` to the tick bus instead of polling.
+/// A bounded history of recent ticks per token is available through
+/// `recent_ticks` / `ticks_since` once enabled with `with_history`; the
+/// default store keeps none.
+#[derive(Debug)]
 pub struct TickStore {
-    by_token: DashMap<i32, TokenState>,
+    by_token: DashMap<i32, TokenEntry>,
+    bus: TickBus,
+    history: TickHistoryConfig,
+}
+
+impl Default for TickStore {
+    fn default() -> Self {
+        Self {
+            by_token: DashMap::new(),
+            bus: TickBus::default(),
+            history: TickHistoryConfig::disabled(),
+        }
+    }
 }
 
 impl TickStore {
+    /// History capacities per instrument type. Applies to tokens seeded or
+    /// first seen afterwards, so set it before `seed_meta`.
+    pub fn with_history(mut self, config: TickHistoryConfig) -> Self {
+        self.history = config;
+        self
+    }
+
+    fn new_entry(&self, meta: TokenMeta) -> TokenEntry {
+        let capacity = self.history.capacity_for(&meta.instrument_type);
+        TokenEntry {
+            state: TokenState::new(meta),
+            history: TickHistory::with_capacity(capacity),
+        }
+    }
+
     /// Seed metadata for subscribed instruments.
     ///
     /// Call this once before websocket starts so the store has the
@@ -172,78 +296,126 @@ impl TickStore {
         for meta in metas {
             self.by_token
                 .entry(meta.instrument_token)
-                .or_insert_with(|| TokenState::new(meta));
+                .or_insert_with(|| self.new_entry(meta));
+        }
+    }
+
+    /// Seed a token with a snapshot tick (e.g. from the REST quote API).
+    ///
+    /// Only applies when the token has not received a tick yet, so a snapshot
+    /// never overwrites live websocket data.
+    pub fn seed_tick(&self, tick: Tick) {
+        if let Some(mut entry) = self.by_token.get_mut(&tick.instrument_token) {
+            if entry.state.last_tick.is_none() {
+                entry.state.last_tick = Some(tick);
+            }
         }
     }
 
     /// Update a token state with the latest tick.
     ///
-    /// This updates derived metrics (spread + ROC) incrementally.
+    /// This updates derived metrics (spread + ROC) incrementally, appends
+    /// to the token's history and publishes the tick to bus subscribers.
+    ///
+    /// Publishing happens after the entry is updated and its lock released,
+    /// so a subscriber reading `get_state` sees this tick, never the previous one.
     pub fn update_tick(&self, tick: Tick) {
-        let token = tick.instrument_token;
-        if let Some(mut state) = self.by_token.get_mut(&token) {
-            // ROC calculations require previous values.
-            if let Some(prev) = state.last_tick.as_ref() {
-                let prev_received_ns = prev.received_ns;
-                let prev_last_price = prev.last_price;
-                let prev_oi = prev.open_interest;
-                let prev_vol = prev.volume_traded;
-
-                let dt_ns = tick.received_ns.saturating_sub(prev_received_ns);
-                let dt_s = (dt_ns as f64) / 1_000_000_000.0;
-                if dt_s > 0.0 {
-                    state.derived.price_roc_per_s = Some((tick.last_price - prev_last_price) / dt_s);
-
-                    match (tick.open_interest, prev_oi) {
-                        (Some(oi), Some(poi)) => {
-                            state.derived.oi_roc_per_s = Some(((oi as f64) - (poi as f64)) / dt_s)
-                        }
-                        _ => {}
-                    }
+        // A FULL tick with depth is a few hundred bytes: only copy it for the
+        // bus when a subscriber will receive it.
+        let published = self.bus.wants(tick.instrument_token).then(|| tick.clone());
+        self.apply_tick(tick);
+        if let Some(tick) = published {
+            self.bus.publish(&tick);
+        }
+    }
 
-                    match (tick.volume_traded, prev_vol) {
-                        (Some(v), Some(pv)) => {
-                            state.derived.vol_roc_per_s = Some(((v as f64) - (pv as f64)) / dt_s)
-                        }
-                        _ => {}
-                    }
+    /// Apply one packet of a binary frame, writing its fields straight into
+    /// the token's stored tick instead of building an owned `Tick` first.
+    ///
+    /// Same effect as `update_tick(packet.to_tick(received_ns))`; the tick is
+    /// only copied out when a bus subscriber wants it.
+    pub fn update_packet(&self, packet: &TickPacket<'_>, received_ns: u64) {
+        let token = packet.instrument_token();
+        let published = {
+            let Some(mut entry) = self.by_token.get_mut(&token) else {
+                // First tick of an unknown token: rare, take the owned path.
+                return self.update_tick(packet.to_tick(received_ns));
+            };
+            let entry = &mut *entry;
+            let state = &mut entry.state;
+            let prev = state.last_tick.as_ref().map(RocBase::of);
+            let tick = match state.last_tick.as_mut() {
+                Some(tick) => {
+                    packet.write_to(tick, received_ns);
+                    tick
                 }
-            }
-
-            // Spread from depth (FULL mode).
-            if let Some(depth) = tick.depth.as_ref() {
-                let bid = depth.buy[0].price;
-                let ask = depth.sell[0].price;
-                state.derived.best_bid = Some(bid);
-                state.derived.best_ask = Some(ask);
-                let spread = ask - bid;
-                state.derived.spread = Some(spread);
-                state.derived.spread_bps = if tick.last_price > 0.0 {
-                    Some((spread / tick.last_price) * 10_000.0)
-                } else {
-                    None
-                };
-            }
+                None => state.last_tick.insert(packet.to_tick(received_ns)),
+            };
+            update_derived(&mut state.derived, prev, tick);
+            entry.history.push(TickSummary::from(&*tick));
+            self.bus.wants(token).then(|| tick.clone())
+        };
+        if let Some(tick) = published {
+            self.bus.publish(&tick);
+        }
+    }
 
+    fn apply_tick(&self, tick: Tick) {
+        let token = tick.instrument_token;
+        if let Some(mut entry) = self.by_token.get_mut(&token) {
+            entry.history.push(TickSummary::from(&tick));
+            let state = &mut entry.state;
+            let prev = state.last_tick.as_ref().map(RocBase::of);
+            update_derived(&mut state.derived, prev, &tick);
             state.last_tick = Some(tick);
             return;
         }
 
         // Fallback: unknown token. Insert minimal meta so we still store the tick.
         let meta = TokenMeta::new(token, "", "UNKNOWN", Option::<Arc<str>>::None, None);
-        let mut state = TokenState::new(meta);
-        state.last_tick = Some(tick);
-        self.by_token.insert(token, state);
+        let mut entry = self.new_entry(meta);
+        entry.history.push(TickSummary::from(&tick));
+        entry.state.last_tick = Some(tick);
+        self.by_token.insert(token, entry);
+    }
+
+    /// Stream ticks as they are applied, with the default queue depth (1024).
+    pub fn subscribe(&self, filter: TickFilter) -> TickSubscription {
+        self.bus.subscribe(filter, DEFAULT_SUBSCRIBER_CAPACITY)
+    }
+
+    /// Fan-out bus behind `subscribe` (custom queue depth, stats).
+    pub fn bus(&self) -> &TickBus {
+        &self.bus
     }
 
     pub fn get_state(&self, instrument_token: i32) -> Option<TokenState> {
-        self.by_token.get(&instrument_token).map(|v| v.clone())
+        self.by_token
+            .get(&instrument_token)
+            .map(|v| v.state.clone())
+    }
+
+    /// The token's last `n` ticks (fewer if the history holds less), oldest first.
+    pub fn recent_ticks(&self, instrument_token: i32, n: usize) -> Vec<TickSummary> {
+        self.by_token
+            .get(&instrument_token)
+            .map(|v| v.history.last(n))
+            .unwrap_or_default()
+    }
+
+    /// The token's ticks received at or after `since_ns` (UNIX ns), oldest
+    /// first. Limited to what the history still holds.
+    pub fn ticks_since(&self, instrument_token: i32, since_ns: u64) -> Vec<TickSummary> {
+        self.by_token
+            .get(&instrument_token)
+            .map(|v| v.history.since(since_ns))
+            .unwrap_or_default()
     }
 
     pub fn get_symbol(&self, instrument_token: i32) -> Option<Arc<str>> {
         self.by_token
             .get(&instrument_token)
-            .map(|s| s.meta.tradingsymbol.clone())
+            .map(|s| s.state.meta.tradingsymbol.clone())
             .filter(|s| !s.trim().is_empty())
     }
 
@@ -255,7 +427,7 @@ impl TickStore {
     pub fn received_token_count(&self) -> usize {
         self.by_token
             .iter()
-            .filter(|kv| kv.value().last_tick.is_some())
+            .filter(|kv| kv.value().state.last_tick.is_some())
             .count()
     }
 
@@ -264,6 +436,25 @@ impl TickStore {
     }
 }
 
+/// Packet counts for one decoded binary frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Packets found in the frame (complete ones only).
+    pub packets: usize,
+    /// Packets skipped because their length matches no known layout.
+    pub unknown_length: usize,
+    /// First skipped packet in the frame, for reporting.
+    pub first_unknown: Option<UnknownPacket>,
+}
+
+/// A packet whose length matches none of the layouts in [`decode_binary_ticks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownPacket {
+    pub len: usize,
+    /// Instrument token from the first 4 bytes, when the packet has them.
+    pub instrument_token: Option<i32>,
+}
+
 /// Decode Kite's binary ticker payload into a list of ticks.
 ///
 /// Kite packs multiple tick "packets" in a single binary frame:
@@ -271,249 +462,371 @@ impl TickStore {
 /// - For each packet:
 ///   - 2 bytes: packet length (u16, big-endian)
 ///   - N bytes: packet payload
+///
+/// The packet layout is inferred from its length:
+/// - 8: LTP
+/// - 28: index quote
+/// - 32: index full (index quote + exchange timestamp)
+/// - 44: quote
+/// - 184: full (quote + timestamps + OI + 5x2 depth)
+///
+/// Other lengths are skipped; [`decode_binary_ticks_with_stats`] reports them.
 pub fn decode_binary_ticks(payload: &[u8], received_ns: u64) -> Vec<Tick> {
+    decode_binary_ticks_with_stats(payload, received_ns).0
+}
+
+/// Same as [`decode_binary_ticks`], also reporting packet counts for feed metrics.
+pub fn decode_binary_ticks_with_stats(payload: &[u8], received_ns: u64) -> (Vec<Tick>, FrameStats) {
     let mut out = Vec::new();
-    if payload.len() < 2 {
-        return out;
+    let stats = visit_binary_ticks(payload, |p| out.push(p.to_tick(received_ns)));
+    (out, stats)
+}
+
+/// Exchange segment, encoded in the low byte of every instrument token.
+pub const SEGMENT_NSE: i32 = 1;
+pub const SEGMENT_NFO: i32 = 2;
+pub const SEGMENT_CDS: i32 = 3;
+pub const SEGMENT_BSE: i32 = 4;
+pub const SEGMENT_BFO: i32 = 5;
+pub const SEGMENT_BCD: i32 = 6;
+pub const SEGMENT_MCX: i32 = 7;
+pub const SEGMENT_MCXSX: i32 = 8;
+pub const SEGMENT_INDICES: i32 = 9;
+
+/// Segment of an instrument token (`token & 0xff`).
+pub fn segment_of(instrument_token: i32) -> i32 {
+    instrument_token & 0xff
+}
+
+/// Divisor that turns the integer prices in a binary packet into rupees.
+///
+/// Currency derivatives are quoted to 7 decimals (CDS) or 4 (BCD); every
+/// other segment sends paise.
+pub fn price_divisor(instrument_token: i32) -> f64 {
+    match segment_of(instrument_token) {
+        SEGMENT_CDS => 10_000_000.0,
+        SEGMENT_BCD => 10_000.0,
+        _ => 100.0,
     }
+}
+
+/// Fast timestamp helper for the websocket hot path.
+pub fn now_unix_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
 
-    let mut offset = 0usize;
-    let n_packets = read_u16_be(payload, &mut offset).unwrap_or(0) as usize;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for _ in 0..n_packets {
-        let Some(packet_len) = read_u16_be(payload, &mut offset) else {
-            break;
-        };
-        let packet_len = packet_len as usize;
-        if offset + packet_len > payload.len() {
-            break;
+    fn token(exchange_token: i32, segment: i32) -> i32 {
+        (exchange_token << 8) | segment
+    }
+
+    fn frame(packets: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = (packets.len() as u16).to_be_bytes().to_vec();
+        for p in packets {
+            out.extend_from_slice(&(p.len() as u16).to_be_bytes());
+            out.extend_from_slice(p);
         }
-        let packet = &payload[offset..offset + packet_len];
-        offset += packet_len;
+        out
+    }
+
+    fn put(p: &mut Vec<u8>, v: i32) {
+        p.extend_from_slice(&v.to_be_bytes());
+    }
 
-        if let Some(tick) = decode_packet(packet, received_ns) {
-            out.push(tick);
+    /// 184-byte full packet; every price field is `raw`-based so the
+    /// expected decoded value is `raw / divisor`.
+    fn full_packet(token: i32, raw: i32) -> Vec<u8> {
+        let mut p = Vec::with_capacity(184);
+        put(&mut p, token);
+        put(&mut p, raw); // ltp
+        put(&mut p, 10); // last qty
+        put(&mut p, raw + 1); // avg
+        put(&mut p, 1_000); // volume
+        put(&mut p, 400); // buy qty
+        put(&mut p, 600); // sell qty
+        put(&mut p, raw + 2); // open
+        put(&mut p, raw + 3); // high
+        put(&mut p, raw - 3); // low
+        put(&mut p, raw - 1); // close
+        for _ in 0..5 {
+            put(&mut p, 0); // ltt, oi, oi high, oi low, exchange ts
         }
+        for level in 0..10 {
+            put(&mut p, 50);
+            put(&mut p, raw + level);
+            p.extend_from_slice(&1u16.to_be_bytes());
+            p.extend_from_slice(&0u16.to_be_bytes());
+        }
+        assert_eq!(p.len(), 184);
+        p
     }
 
-    out
-}
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
 
-fn decode_packet(packet: &[u8], received_ns: u64) -> Option<Tick> {
-    // All known packet types start with 4-byte instrument token.
-    if packet.len() < 8 {
-        return None;
-    }
-
-    let mut offset = 0usize;
-    let token_u32 = read_u32_be(packet, &mut offset)?;
-    let instrument_token = token_u32 as i32;
-
-    // Next always contains last_price (i32 paise).
-    let last_price_i32 = read_i32_be(packet, &mut offset)?;
-    let last_price = (last_price_i32 as f64) / 100.0;
-
-    // Packet type is inferred from length (Kite convention).
-    match packet.len() {
-        8 => Some(Tick::new_ltp(instrument_token, last_price, received_ns)),
-
-        // Index packet (commonly 28 bytes): token + (6 * i32 fields)
-        // last_price, high, low, open, close, change
-        28 => {
-            let high = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let low = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let open = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let close = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let change = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-
-            Some(Tick {
-                instrument_token,
-                mode: TickMode::Quote,
-                last_price,
-                last_quantity: None,
-                average_traded_price: None,
-                volume_traded: None,
-                total_buy_quantity: None,
-                total_sell_quantity: None,
-                ohlc: Some(Ohlc {
-                    open,
-                    high,
-                    low,
-                    close,
-                }),
-                change: Some(change),
-                last_trade_time: None,
-                open_interest: None,
-                oi_day_high: None,
-                oi_day_low: None,
-                exchange_timestamp: None,
-                depth: None,
-                received_ns,
-            })
+    fn assert_full_scaled(token: i32, raw: i32, divisor: f64) {
+        let ticks = decode_binary_ticks(&frame(&[full_packet(token, raw)]), 0);
+        assert_eq!(ticks.len(), 1);
+        let t = &ticks[0];
+        let px = |v: i32| v as f64 / divisor;
+        assert_close(t.last_price, px(raw));
+        assert_close(t.average_traded_price.unwrap(), px(raw + 1));
+        let ohlc = t.ohlc.unwrap();
+        assert_close(ohlc.open, px(raw + 2));
+        assert_close(ohlc.high, px(raw + 3));
+        assert_close(ohlc.low, px(raw - 3));
+        assert_close(ohlc.close, px(raw - 1));
+        let depth = t.depth.as_ref().unwrap();
+        for (i, level) in depth.buy.iter().chain(depth.sell.iter()).enumerate() {
+            assert_close(level.price, px(raw + i as i32));
         }
+    }
 
-        // Quote packet (commonly 44 bytes)
-        44 => {
-            let last_quantity = read_u32_be(packet, &mut offset)?;
-            let avg = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let volume = read_u32_be(packet, &mut offset)?;
-            let buy_qty = read_u32_be(packet, &mut offset)?;
-            let sell_qty = read_u32_be(packet, &mut offset)?;
-            let open = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let high = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let low = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let close = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-
-            Some(Tick {
-                instrument_token,
-                mode: TickMode::Quote,
-                last_price,
-                last_quantity: Some(last_quantity),
-                average_traded_price: Some(avg),
-                volume_traded: Some(volume),
-                total_buy_quantity: Some(buy_qty),
-                total_sell_quantity: Some(sell_qty),
-                ohlc: Some(Ohlc {
-                    open,
-                    high,
-                    low,
-                    close,
-                }),
-                change: Some(if close != 0.0 {
-                    (last_price - close) / close
-                } else {
-                    0.0
-                }),
-                last_trade_time: None,
-                open_interest: None,
-                oi_day_high: None,
-                oi_day_low: None,
-                exchange_timestamp: None,
-                depth: None,
-                received_ns,
-            })
+    #[test]
+    fn segment_is_low_byte_of_token() {
+        assert_eq!(segment_of(408065), SEGMENT_NSE);
+        assert_eq!(segment_of(256265), SEGMENT_INDICES);
+        assert_eq!(segment_of(token(1234, SEGMENT_CDS)), SEGMENT_CDS);
+    }
+
+    #[test]
+    fn equity_and_fno_prices_are_paise() {
+        let paise_segments =
+            (SEGMENT_NSE..=SEGMENT_INDICES).filter(|s| ![SEGMENT_CDS, SEGMENT_BCD].contains(s));
+        for seg in paise_segments {
+            assert_eq!(price_divisor(token(1594, seg)), 100.0);
+            assert_full_scaled(token(1594, seg), 150_235, 100.0);
         }
+    }
 
-        // Full packet (commonly 184 bytes): quote + timestamps + OI + depth(10 levels).
-        184 => {
-            let last_quantity = read_u32_be(packet, &mut offset)?;
-            let avg = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let volume = read_u32_be(packet, &mut offset)?;
-            let buy_qty = read_u32_be(packet, &mut offset)?;
-            let sell_qty = read_u32_be(packet, &mut offset)?;
-            let open = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let high = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let low = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-            let close = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-
-            let last_trade_time = read_u32_be(packet, &mut offset)?;
-            let oi = read_u32_be(packet, &mut offset)?;
-            let oi_day_high = read_u32_be(packet, &mut offset)?;
-            let oi_day_low = read_u32_be(packet, &mut offset)?;
-            let exchange_timestamp = read_u32_be(packet, &mut offset)?;
-
-            // Depth: 10 levels, 5 buy + 5 sell.
-            let mut buy = [DepthLevel {
-                quantity: 0,
-                price: 0.0,
-                orders: 0,
-            }; 5];
-            let mut sell = [DepthLevel {
-                quantity: 0,
-                price: 0.0,
-                orders: 0,
-            }; 5];
-
-            // Each level is 12 bytes: quantity(u32) + price(i32 paise) + orders(u16) + reserved(u16)
-            for i in 0..5 {
-                let q = read_u32_be(packet, &mut offset)?;
-                let p = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-                let orders = read_u16_be(packet, &mut offset)?;
-                let _reserved = read_u16_be(packet, &mut offset)?;
-                buy[i] = DepthLevel {
-                    quantity: q,
-                    price: p,
-                    orders,
-                };
-            }
-            for i in 0..5 {
-                let q = read_u32_be(packet, &mut offset)?;
-                let p = (read_i32_be(packet, &mut offset)? as f64) / 100.0;
-                let orders = read_u16_be(packet, &mut offset)?;
-                let _reserved = read_u16_be(packet, &mut offset)?;
-                sell[i] = DepthLevel {
-                    quantity: q,
-                    price: p,
-                    orders,
-                };
+    #[test]
+    fn cds_prices_use_seven_decimals() {
+        let t = token(2885, SEGMENT_CDS);
+        assert_eq!(price_divisor(t), 10_000_000.0);
+        // USDINR 83.2525 -> 832_525_000
+        assert_full_scaled(t, 832_525_000, 10_000_000.0);
+    }
+
+    #[test]
+    fn bcd_prices_use_four_decimals() {
+        let t = token(2885, SEGMENT_BCD);
+        assert_eq!(price_divisor(t), 10_000.0);
+        assert_full_scaled(t, 832_525, 10_000.0);
+    }
+
+    #[test]
+    fn cds_ltp_and_quote_packets_are_scaled() {
+        let t = token(77, SEGMENT_CDS);
+        let mut ltp = Vec::new();
+        put(&mut ltp, t);
+        put(&mut ltp, 832_525_000);
+
+        let mut quote = ltp.clone();
+        for v in [1, 832_500_000, 10, 4, 6] {
+            put(&mut quote, v); // last qty, avg, volume, buy qty, sell qty
+        }
+        for v in [832_000_000, 833_000_000, 831_000_000, 832_100_000] {
+            put(&mut quote, v); // open, high, low, close
+        }
+        assert_eq!(quote.len(), 44);
+
+        let ticks = decode_binary_ticks(&frame(&[ltp, quote]), 0);
+        assert_eq!(ticks.len(), 2);
+        assert_close(ticks[0].last_price, 83.2525);
+        assert_close(ticks[1].average_traded_price.unwrap(), 83.25);
+        let ohlc = ticks[1].ohlc.unwrap();
+        assert_close(ohlc.open, 83.2);
+        assert_close(ohlc.high, 83.3);
+        assert_close(ohlc.low, 83.1);
+        assert_close(ohlc.close, 83.21);
+    }
+
+    #[test]
+    fn index_packet_prices_are_paise() {
+        let mut p = Vec::new();
+        put(&mut p, 256265);
+        for v in [2_346_560, 2_350_000, 2_340_000, 2_341_000, 2_342_000, 4_560] {
+            put(&mut p, v);
+        }
+        let ticks = decode_binary_ticks(&frame(&[p]), 0);
+        assert_close(ticks[0].last_price, 23465.6);
+        assert_close(ticks[0].ohlc.unwrap().high, 23500.0);
+        assert_close(ticks[0].change.unwrap(), 45.6);
+        assert_eq!(ticks[0].mode, TickMode::Quote);
+        assert_eq!(ticks[0].exchange_timestamp, None);
+    }
+
+    #[test]
+    fn index_full_packet_carries_exchange_timestamp() {
+        let mut p = Vec::new();
+        put(&mut p, 256265);
+        for v in [2_346_560, 2_350_000, 2_340_000, 2_341_000, 2_342_000, 4_560] {
+            put(&mut p, v);
+        }
+        put(&mut p, 1_718_000_000);
+        assert_eq!(p.len(), 32);
+
+        let (ticks, stats) = decode_binary_ticks_with_stats(&frame(&[p]), 0);
+        assert_eq!(stats.unknown_length, 0);
+        assert_eq!(ticks[0].mode, TickMode::Full);
+        assert_close(ticks[0].last_price, 23465.6);
+        assert_close(ticks[0].ohlc.unwrap().close, 23420.0);
+        assert_eq!(ticks[0].exchange_timestamp, Some(1_718_000_000));
+    }
+
+    #[test]
+    fn visitor_reads_fields_without_materializing() {
+        let full = full_packet(token(1594, SEGMENT_NSE), 150_235);
+        let mut ltp = Vec::new();
+        put(&mut ltp, 738561);
+        put(&mut ltp, 287_560);
+        let payload = frame(&[full, ltp]);
+
+        let mut seen = Vec::new();
+        let stats = visit_binary_ticks(&payload, |p| {
+            seen.push((p.layout(), p.instrument_token(), p.last_price()));
+            if p.layout() == PacketLayout::Ltp {
+                assert!(p.ohlc().is_none() && p.depth().is_none() && p.volume_traded().is_none());
+            } else {
+                assert_close(p.depth_level(true, 4).unwrap().price, 1502.44);
+                assert!(p.depth_level(true, 5).is_none());
             }
+        });
+        assert_eq!(stats.packets, 2);
+        assert_eq!(
+            seen,
+            vec![
+                (PacketLayout::Full, token(1594, SEGMENT_NSE), 1502.35),
+                (PacketLayout::Ltp, 738561, 2875.6),
+            ]
+        );
+    }
 
-            Some(Tick {
-                instrument_token,
-                mode: TickMode::Full,
-                last_price,
-                last_quantity: Some(last_quantity),
-                average_traded_price: Some(avg),
-                volume_traded: Some(volume),
-                total_buy_quantity: Some(buy_qty),
-                total_sell_quantity: Some(sell_qty),
-                ohlc: Some(Ohlc {
-                    open,
-                    high,
-                    low,
-                    close,
-                }),
-                change: Some(if close != 0.0 {
-                    (last_price - close) / close
-                } else {
-                    0.0
-                }),
-                last_trade_time: Some(last_trade_time),
-                open_interest: Some(oi),
-                oi_day_high: Some(oi_day_high),
-                oi_day_low: Some(oi_day_low),
-                exchange_timestamp: Some(exchange_timestamp),
-                depth: Some(MarketDepth { buy, sell }),
-                received_ns,
+    #[test]
+    fn unknown_layouts_are_counted_and_reported() {
+        let mut odd = Vec::new();
+        put(&mut odd, 408065);
+        put(&mut odd, 150_235);
+        put(&mut odd, 0);
+        let mut ltp = Vec::new();
+        put(&mut ltp, 738561);
+        put(&mut ltp, 287_560);
+
+        let (ticks, stats) = decode_binary_ticks_with_stats(&frame(&[odd, ltp, vec![1, 2]]), 0);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].instrument_token, 738561);
+        assert_eq!(stats.packets, 3);
+        assert_eq!(stats.unknown_length, 2);
+        assert_eq!(
+            stats.first_unknown,
+            Some(UnknownPacket {
+                len: 12,
+                instrument_token: Some(408065),
             })
+        );
+    }
+
+    /// A subscriber that reads the store back on every tick must never see
+    /// state older than the tick it was handed, for seeded and unknown tokens.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn subscribers_see_the_store_already_updated() {
+        const TICKS: u32 = 5_000;
+        let store = Arc::new(TickStore::default());
+        store.seed_meta([TokenMeta::new(1, "SEEDED", "EQ", None::<&str>, None)]);
+        let mut sub = store.bus().subscribe(TickFilter::All, TICKS as usize * 2);
+
+        let reader = {
+            let store = store.clone();
+            tokio::spawn(async move {
+                let mut stale = 0;
+                for _ in 0..TICKS * 2 {
+                    let tick = sub.recv().await.unwrap();
+                    let state = store.get_state(tick.instrument_token);
+                    let seen = state.and_then(|s| s.last_tick).map(|t| t.last_price);
+                    if seen.map_or(true, |p| p < tick.last_price) {
+                        stale += 1;
+                    }
+                }
+                stale
+            })
+        };
+        for i in 1..=TICKS {
+            store.update_tick(Tick::new_ltp(1, i as f64, i as u64));
+            store.update_tick(Tick::new_ltp(2, i as f64, i as u64));
         }
 
-        // Unknown packet size: ignore safely.
-        _ => None,
+        assert_eq!(reader.await.unwrap(), 0);
+        assert!(store.get_state(2).is_some());
     }
-}
 
-/// Fast timestamp helper for the websocket hot path.
-pub fn now_unix_ns() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos() as u64
-}
+    #[test]
+    fn update_packet_matches_update_tick() {
+        let seeded = token(1594, SEGMENT_NSE);
+        let ltp = |token: i32, raw: i32| {
+            let mut p = Vec::new();
+            put(&mut p, token);
+            put(&mut p, raw);
+            p
+        };
+        let frames = [
+            frame(&[full_packet(seeded, 150_235), ltp(738561, 287_560)]),
+            frame(&[full_packet(seeded, 150_300), ltp(738561, 287_600)]),
+            // FULL then LTP on the same token: depth and OI must be cleared.
+            frame(&[ltp(seeded, 150_310)]),
+        ];
+        let store = || {
+            let store = TickStore::default().with_history(TickHistoryConfig::default());
+            store.seed_meta([TokenMeta::new(seeded, "INFY", "EQ", None::<&str>, None)]);
+            store
+        };
+        let (by_tick, by_packet) = (store(), store());
+        let mut sub = by_packet.subscribe(TickFilter::All);
+        for (i, f) in frames.iter().enumerate() {
+            let ns = (i as u64 + 1) * 1_000_000_000;
+            visit_binary_ticks(f, |p| by_tick.update_tick(p.to_tick(ns)));
+            visit_binary_ticks(f, |p| by_packet.update_packet(&p, ns));
+        }
 
-fn read_u16_be(buf: &[u8], offset: &mut usize) -> Option<u16> {
-    if *offset + 2 > buf.len() {
-        return None;
+        for t in [seeded, 738561] {
+            assert_eq!(
+                format!("{:?}", by_tick.get_state(t)),
+                format!("{:?}", by_packet.get_state(t))
+            );
+            assert_eq!(by_tick.recent_ticks(t, 10), by_packet.recent_ticks(t, 10));
+        }
+        let state = by_packet.get_state(seeded).unwrap();
+        assert!(state.derived.price_roc_per_s.is_some());
+        assert!(state.last_tick.unwrap().depth.is_none());
+        let published: Vec<i32> = std::iter::from_fn(|| sub.try_recv())
+            .map(|t| t.instrument_token)
+            .collect();
+        assert_eq!(published, [seeded, 738561, seeded, 738561, seeded]);
     }
-    let v = u16::from_be_bytes([buf[*offset], buf[*offset + 1]]);
-    *offset += 2;
-    Some(v)
-}
-
-fn read_u32_be(buf: &[u8], offset: &mut usize) -> Option<u32> {
-    if *offset + 4 > buf.len() {
-        return None;
-    }
-    let v = u32::from_be_bytes([
-        buf[*offset],
-        buf[*offset + 1],
-        buf[*offset + 2],
-        buf[*offset + 3],
-    ]);
-    *offset += 4;
-    Some(v)
-}
 
-fn read_i32_be(buf: &[u8], offset: &mut usize) -> Option<i32> {
-    read_u32_be(buf, offset).map(|v| v as i32)
+    #[test]
+    fn ticks_are_only_published_to_interested_subscribers() {
+        let store = TickStore::default();
+        store.update_tick(Tick::new_ltp(1, 100.0, 1));
+        assert_eq!(store.bus().stats().published, 0);
+
+        let mut sub = store.bus().subscribe(TickFilter::tokens([1]), 8);
+        store.update_tick(Tick::new_ltp(2, 10.0, 2));
+        assert_eq!(store.bus().stats().published, 0);
+        store.update_tick(Tick::new_ltp(1, 101.0, 3));
+        assert_eq!(store.bus().stats().published, 1);
+        assert_eq!(sub.try_recv().map(|t| t.last_price), Some(101.0));
+        assert!(sub.try_recv().is_none());
+        // Every tick still reaches the store.
+        assert!(store.get_state(2).is_some());
+    }
 }
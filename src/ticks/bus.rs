@@ -0,0 +1,186 @@
+use super::Tick;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+/// Default per-subscriber queue depth.
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+/// Which ticks a subscriber wants.
+#[derive(Debug, Clone)]
+pub enum TickFilter {
+    All,
+    Tokens(HashSet<i32>),
+}
+
+impl TickFilter {
+    pub fn tokens(tokens: impl IntoIterator<Item = i32>) -> Self {
+        TickFilter::Tokens(tokens.into_iter().collect())
+    }
+
+    fn matches(&self, instrument_token: i32) -> bool {
+        match self {
+            TickFilter::All => true,
+            TickFilter::Tokens(set) => set.contains(&instrument_token),
+        }
+    }
+}
+
+/// Per-subscriber counters, shared between the bus and the subscription.
+#[derive(Debug, Default)]
+struct SubscriberCounters {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    filter: TickFilter,
+    tx: mpsc::Sender<Tick>,
+    counters: Arc<SubscriberCounters>,
+}
+
+/// Bus-wide counters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickBusStats {
+    pub subscribers: usize,
+    /// Ticks handed to `publish`. `TickStore` only publishes ticks that some
+    /// subscriber wants.
+    pub published: u64,
+    /// Deliveries into subscriber queues (one tick can count once per subscriber).
+    pub delivered: u64,
+    /// Deliveries skipped because a subscriber's queue was full.
+    pub dropped: u64,
+}
+
+/// Fan-out of decoded ticks to streaming consumers.
+///
+/// Each subscriber gets its own bounded queue so a slow consumer never blocks
+/// the websocket task or other subscribers: when its queue is full the tick is
+/// dropped for that subscriber and counted. Subscribers whose receiver has
+/// been dropped are pruned on the next publish.
+#[derive(Debug, Default)]
+pub struct TickBus {
+    subscribers: RwLock<Vec<Subscriber>>,
+    published: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl TickBus {
+    /// Subscribe with a queue of `capacity` ticks (clamped to at least 1).
+    pub fn subscribe(&self, filter: TickFilter, capacity: usize) -> TickSubscription {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let counters = Arc::new(SubscriberCounters::default());
+        self.subscribers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Subscriber {
+                filter,
+                tx,
+                counters: counters.clone(),
+            });
+        TickSubscription {
+            rx,
+            counters,
+            received: 0,
+        }
+    }
+
+    /// Whether any subscriber's filter matches `instrument_token`, so callers
+    /// can skip building a tick nobody receives.
+    pub fn wants(&self, instrument_token: i32) -> bool {
+        self.subscribers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|s| s.filter.matches(instrument_token))
+    }
+
+    /// Offer `tick` to every matching subscriber without waiting.
+    pub fn publish(&self, tick: &Tick) {
+        self.published.fetch_add(1, Ordering::Relaxed);
+        let mut saw_closed = false;
+        {
+            let subs = self.subscribers.read().unwrap_or_else(|e| e.into_inner());
+            for sub in subs.iter() {
+                if !sub.filter.matches(tick.instrument_token) {
+                    continue;
+                }
+                match sub.tx.try_send(tick.clone()) {
+                    Ok(()) => {
+                        sub.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                        self.delivered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        sub.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => saw_closed = true,
+                }
+            }
+        }
+        if saw_closed {
+            self.subscribers
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|s| !s.tx.is_closed());
+        }
+    }
+
+    pub fn stats(&self) -> TickBusStats {
+        TickBusStats {
+            subscribers: self
+                .subscribers
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .len(),
+            published: self.published.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Receiving side of a [`TickBus`] subscription. Dropping it unsubscribes.
+#[derive(Debug)]
+pub struct TickSubscription {
+    rx: mpsc::Receiver<Tick>,
+    counters: Arc<SubscriberCounters>,
+    received: u64,
+}
+
+impl TickSubscription {
+    /// Next tick, or `None` once the bus is gone.
+    pub async fn recv(&mut self) -> Option<Tick> {
+        let tick = self.rx.recv().await;
+        if tick.is_some() {
+            self.received += 1;
+        }
+        tick
+    }
+
+    pub fn try_recv(&mut self) -> Option<Tick> {
+        let tick = self.rx.try_recv().ok();
+        if tick.is_some() {
+            self.received += 1;
+        }
+        tick
+    }
+
+    /// Ticks queued for this subscriber but not yet received.
+    pub fn lag(&self) -> u64 {
+        self.delivered().saturating_sub(self.received)
+    }
+
+    /// Ticks delivered into this subscriber's queue so far.
+    pub fn delivered(&self) -> u64 {
+        self.counters.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Ticks dropped because this subscriber's queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,119 @@
+//! Serialise [`Tick`] values into Kite's binary ticker frame layout.
+//!
+//! The inverse of [`super::decode_binary_ticks`]: useful for tests, benches
+//! and simulators that need realistic frames. Prices are written as integers
+//! scaled by the token's segment divisor (paise for most segments).
+
+use super::{
+    price_divisor, segment_of, DepthLevel, Ohlc, PacketLayout, Tick, TickMode, SEGMENT_INDICES,
+};
+
+/// Layout `tick` is written in: its mode, with index tokens (segment 9)
+/// using the short index packets.
+pub fn layout_for(tick: &Tick) -> PacketLayout {
+    let is_index = segment_of(tick.instrument_token) == SEGMENT_INDICES;
+    match (tick.mode, is_index) {
+        (TickMode::Ltp, _) => PacketLayout::Ltp,
+        (TickMode::Quote, true) => PacketLayout::IndexQuote,
+        (TickMode::Full, true) => PacketLayout::IndexFull,
+        (TickMode::Quote, false) => PacketLayout::Quote,
+        (TickMode::Full, false) => PacketLayout::Full,
+    }
+}
+
+/// Append one packet (without its length prefix) for `tick` to `out`.
+///
+/// Fields the tick lacks are written as 0. For index packets `change` is
+/// written as is; quote/full packets carry no change field (the decoder
+/// derives it from the close).
+pub fn encode_packet(tick: &Tick, out: &mut Vec<u8>) {
+    let divisor = price_divisor(tick.instrument_token);
+    let price = |out: &mut Vec<u8>, v: f64| put_i32(out, (v * divisor).round() as i32);
+    let ohlc = tick.ohlc.unwrap_or(Ohlc {
+        open: 0.0,
+        high: 0.0,
+        low: 0.0,
+        close: 0.0,
+    });
+
+    put_u32(out, tick.instrument_token as u32);
+    price(out, tick.last_price);
+
+    let layout = layout_for(tick);
+    match layout {
+        PacketLayout::Ltp => {}
+        PacketLayout::IndexQuote | PacketLayout::IndexFull => {
+            price(out, ohlc.high);
+            price(out, ohlc.low);
+            price(out, ohlc.open);
+            price(out, ohlc.close);
+            price(out, tick.change.unwrap_or(0.0));
+            if layout == PacketLayout::IndexFull {
+                put_u32(out, tick.exchange_timestamp.unwrap_or(0));
+            }
+        }
+        PacketLayout::Quote | PacketLayout::Full => {
+            put_u32(out, tick.last_quantity.unwrap_or(0));
+            price(out, tick.average_traded_price.unwrap_or(0.0));
+            put_u32(out, tick.volume_traded.unwrap_or(0));
+            put_u32(out, tick.total_buy_quantity.unwrap_or(0));
+            put_u32(out, tick.total_sell_quantity.unwrap_or(0));
+            price(out, ohlc.open);
+            price(out, ohlc.high);
+            price(out, ohlc.low);
+            price(out, ohlc.close);
+            if layout == PacketLayout::Quote {
+                return;
+            }
+
+            put_u32(out, tick.last_trade_time.unwrap_or(0));
+            put_u32(out, tick.open_interest.unwrap_or(0));
+            put_u32(out, tick.oi_day_high.unwrap_or(0));
+            put_u32(out, tick.oi_day_low.unwrap_or(0));
+            put_u32(out, tick.exchange_timestamp.unwrap_or(0));
+            let empty = [DepthLevel {
+                quantity: 0,
+                price: 0.0,
+                orders: 0,
+            }; 5];
+            let (buy, sell) = tick
+                .depth
+                .as_ref()
+                .map(|d| (d.buy, d.sell))
+                .unwrap_or((empty, empty));
+            for l in buy.iter().chain(sell.iter()) {
+                put_u32(out, l.quantity);
+                price(out, l.price);
+                out.extend_from_slice(&l.orders.to_be_bytes());
+                out.extend_from_slice(&0u16.to_be_bytes()); // reserved
+            }
+        }
+    }
+}
+
+/// Encode `ticks` as one binary frame: packet count, then length-prefixed packets.
+///
+/// Panics if given more than `u16::MAX` ticks, which a single frame cannot hold.
+pub fn encode_frame<'a>(ticks: impl IntoIterator<Item = &'a Tick>) -> Vec<u8> {
+    let mut out = vec![0u8; 2];
+    let mut count = 0usize;
+    for tick in ticks {
+        let len_at = out.len();
+        out.extend_from_slice(&[0, 0]);
+        encode_packet(tick, &mut out);
+        let len = (out.len() - len_at - 2) as u16;
+        out[len_at..len_at + 2].copy_from_slice(&len.to_be_bytes());
+        count += 1;
+    }
+    let count = u16::try_from(count).expect("binary frame holds at most 65535 packets");
+    out[..2].copy_from_slice(&count.to_be_bytes());
+    out
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn put_i32(out: &mut Vec<u8>, v: i32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
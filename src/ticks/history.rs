@@ -0,0 +1,248 @@
+use super::Tick;
+use std::collections::{HashMap, VecDeque};
+
+/// Compact record of one tick kept in a token's rolling history.
+///
+/// Depth is reduced to the best bid/ask so a few hundred entries per token
+/// stay cheap; the latest full tick is still in [`super::TokenState::last_tick`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickSummary {
+    /// When this process received the tick (UNIX ns).
+    pub received_ns: u64,
+    /// Exchange timestamp (UNIX s), FULL mode only.
+    pub exchange_timestamp: Option<u32>,
+    pub last_price: f64,
+    pub last_quantity: Option<u32>,
+    pub volume_traded: Option<u32>,
+    pub open_interest: Option<u32>,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+}
+
+impl From<&Tick> for TickSummary {
+    fn from(tick: &Tick) -> Self {
+        Self {
+            received_ns: tick.received_ns,
+            exchange_timestamp: tick.exchange_timestamp,
+            last_price: tick.last_price,
+            last_quantity: tick.last_quantity,
+            volume_traded: tick.volume_traded,
+            open_interest: tick.open_interest,
+            best_bid: tick.depth.as_ref().map(|d| d.buy[0].price),
+            best_ask: tick.depth.as_ref().map(|d| d.sell[0].price),
+        }
+    }
+}
+
+/// Bounded ring buffer of a token's most recent ticks, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct TickHistory {
+    ticks: VecDeque<TickSummary>,
+    capacity: usize,
+}
+
+impl TickHistory {
+    /// History of at most `capacity` ticks; 0 keeps nothing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ticks: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Append a tick, evicting the oldest one when full.
+    ///
+    /// `received_ns` comes from the wall clock, which can step backwards (NTP);
+    /// it is clamped to the previous tick's so the history stays ordered.
+    pub fn push(&mut self, mut summary: TickSummary) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(prev) = self.ticks.back() {
+            summary.received_ns = summary.received_ns.max(prev.received_ns);
+        }
+        if self.ticks.len() == self.capacity {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back(summary);
+    }
+
+    /// The last `n` ticks (fewer if not yet received), oldest first.
+    pub fn last(&self, n: usize) -> Vec<TickSummary> {
+        let skip = self.ticks.len().saturating_sub(n);
+        self.ticks.iter().skip(skip).copied().collect()
+    }
+
+    /// Ticks received at or after `since_ns` (UNIX ns), oldest first.
+    pub fn since(&self, since_ns: u64) -> Vec<TickSummary> {
+        let start = self.ticks.partition_point(|t| t.received_ns < since_ns);
+        self.ticks.range(start..).copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// How many ticks of history to keep per token, by instrument type.
+#[derive(Debug, Clone)]
+pub struct TickHistoryConfig {
+    /// Capacity for instrument types without an override.
+    pub default_capacity: usize,
+    /// Overrides keyed by instrument type (`EQ`, `FUT`, `CE`, `PE`, ...).
+    pub by_instrument_type: HashMap<String, usize>,
+}
+
+impl Default for TickHistoryConfig {
+    /// 300 ticks (about five minutes of FULL ticks) for most instruments and
+    /// 60 for options, which make up most of a large subscription.
+    fn default() -> Self {
+        Self {
+            default_capacity: 300,
+            by_instrument_type: [("CE".to_string(), 60), ("PE".to_string(), 60)]
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl TickHistoryConfig {
+    /// Keep no history for any instrument type.
+    pub fn disabled() -> Self {
+        Self {
+            default_capacity: 0,
+            by_instrument_type: HashMap::new(),
+        }
+    }
+
+    /// Env:
+    /// - TICK_HISTORY_CAPACITY (default 300; 0 disables history)
+    /// - TICK_HISTORY_CAPACITY_<INSTRUMENT_TYPE>, e.g. TICK_HISTORY_CAPACITY_CE
+    ///   (defaults: CE=60, PE=60)
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let parse = |v: String| v.trim().parse::<usize>().ok();
+        if let Some(v) = std::env::var("TICK_HISTORY_CAPACITY").ok().and_then(parse) {
+            config.default_capacity = v;
+        }
+        for (key, value) in std::env::vars() {
+            let Some(kind) = key.strip_prefix("TICK_HISTORY_CAPACITY_") else {
+                continue;
+            };
+            if let Some(v) = parse(value).filter(|_| !kind.is_empty()) {
+                config
+                    .by_instrument_type
+                    .insert(kind.to_ascii_uppercase(), v);
+            }
+        }
+        config
+    }
+
+    pub fn capacity_for(&self, instrument_type: &str) -> usize {
+        self.by_instrument_type
+            .get(instrument_type.trim())
+            .copied()
+            .unwrap_or(self.default_capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticks::{TickStore, TokenMeta};
+
+    fn summary(received_ns: u64) -> TickSummary {
+        TickSummary::from(&Tick::new_ltp(1, received_ns as f64, received_ns))
+    }
+
+    fn times(ticks: &[TickSummary]) -> Vec<u64> {
+        ticks.iter().map(|t| t.received_ns).collect()
+    }
+
+    #[test]
+    fn ring_keeps_the_newest_ticks() {
+        let mut h = TickHistory::with_capacity(3);
+        for ns in 1..=5 {
+            h.push(summary(ns));
+        }
+        assert_eq!(h.len(), 3);
+        assert_eq!(times(&h.last(10)), vec![3, 4, 5]);
+        assert_eq!(times(&h.last(2)), vec![4, 5]);
+        assert!(h.last(0).is_empty());
+    }
+
+    #[test]
+    fn since_returns_ticks_at_or_after_the_timestamp() {
+        let mut h = TickHistory::with_capacity(10);
+        for ns in [10, 20, 30, 40] {
+            h.push(summary(ns));
+        }
+        assert_eq!(times(&h.since(20)), vec![20, 30, 40]);
+        assert_eq!(times(&h.since(25)), vec![30, 40]);
+        assert_eq!(times(&h.since(0)), vec![10, 20, 30, 40]);
+        assert!(h.since(41).is_empty());
+    }
+
+    #[test]
+    fn clock_stepping_back_does_not_hide_ticks() {
+        let mut h = TickHistory::with_capacity(10);
+        for ns in [100, 200, 150, 300] {
+            h.push(summary(ns));
+        }
+        // The tick received at 150 (after the clock stepped back) is kept at 200.
+        assert_eq!(times(&h.last(10)), vec![100, 200, 200, 300]);
+        assert_eq!(h.last(2)[0].last_price, 150.0);
+        assert_eq!(times(&h.since(150)), vec![200, 200, 300]);
+        assert_eq!(times(&h.since(101)), vec![200, 200, 300]);
+        assert_eq!(times(&h.since(300)), vec![300]);
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut h = TickHistory::with_capacity(0);
+        h.push(summary(1));
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn default_store_keeps_no_history() {
+        let store = TickStore::default();
+        store.seed_meta([TokenMeta::new(1, "NIFTY FUT", "FUT", None::<&str>, None)]);
+        store.update_tick(Tick::new_ltp(1, 100.0, 1));
+        store.update_tick(Tick::new_ltp(2, 10.0, 1));
+        assert!(store.recent_ticks(1, 10).is_empty());
+        assert!(store.recent_ticks(2, 10).is_empty());
+        assert!(store.get_state(1).unwrap().last_tick.is_some());
+    }
+
+    #[test]
+    fn store_sizes_history_by_instrument_type() {
+        let config = TickHistoryConfig {
+            default_capacity: 4,
+            by_instrument_type: [("CE".to_string(), 2)].into_iter().collect(),
+        };
+        let store = TickStore::default().with_history(config);
+        store.seed_meta([
+            TokenMeta::new(1, "NIFTY FUT", "FUT", None::<&str>, None),
+            TokenMeta::new(2, "NIFTY CE", "CE", None::<&str>, None),
+        ]);
+        for ns in 1..=6 {
+            store.update_tick(Tick::new_ltp(1, 100.0 + ns as f64, ns));
+            store.update_tick(Tick::new_ltp(2, 10.0 + ns as f64, ns));
+        }
+
+        assert_eq!(times(&store.recent_ticks(1, 10)), vec![3, 4, 5, 6]);
+        assert_eq!(times(&store.recent_ticks(2, 10)), vec![5, 6]);
+        assert_eq!(store.recent_ticks(1, 1)[0].last_price, 106.0);
+        assert_eq!(times(&store.ticks_since(1, 5)), vec![5, 6]);
+        assert!(store.recent_ticks(99, 10).is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+use super::{
+    price_divisor, DepthLevel, FrameStats, MarketDepth, Ohlc, Tick, TickMode, UnknownPacket,
+};
+
+/// Binary packet layouts, identified by packet length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketLayout {
+    /// 8 bytes: token + last price.
+    Ltp,
+    /// 28 bytes: token + last price, high, low, open, close, change.
+    IndexQuote,
+    /// 32 bytes: index quote + exchange timestamp.
+    IndexFull,
+    /// 44 bytes: token + last price, quantities, average price and OHLC.
+    Quote,
+    /// 184 bytes: quote + timestamps, OI and 5x2 depth levels.
+    Full,
+}
+
+impl PacketLayout {
+    pub fn from_len(len: usize) -> Option<Self> {
+        match len {
+            8 => Some(PacketLayout::Ltp),
+            28 => Some(PacketLayout::IndexQuote),
+            32 => Some(PacketLayout::IndexFull),
+            44 => Some(PacketLayout::Quote),
+            184 => Some(PacketLayout::Full),
+            _ => None,
+        }
+    }
+
+    pub fn mode(self) -> TickMode {
+        match self {
+            PacketLayout::Ltp => TickMode::Ltp,
+            PacketLayout::IndexQuote | PacketLayout::Quote => TickMode::Quote,
+            PacketLayout::IndexFull | PacketLayout::Full => TickMode::Full,
+        }
+    }
+
+    fn is_index(self) -> bool {
+        matches!(self, PacketLayout::IndexQuote | PacketLayout::IndexFull)
+    }
+}
+
+// Byte offsets shared by quote and full packets.
+const LAST_QUANTITY: usize = 8;
+const AVERAGE_PRICE: usize = 12;
+const VOLUME: usize = 16;
+const BUY_QUANTITY: usize = 20;
+const SELL_QUANTITY: usize = 24;
+const OPEN: usize = 28;
+const LAST_TRADE_TIME: usize = 44;
+const OPEN_INTEREST: usize = 48;
+const OI_DAY_HIGH: usize = 52;
+const OI_DAY_LOW: usize = 56;
+const EXCHANGE_TIMESTAMP: usize = 60;
+const DEPTH: usize = 64;
+/// quantity(u32) + price(i32, scaled) + orders(u16) + reserved(u16)
+const DEPTH_LEVEL_LEN: usize = 12;
+
+// Index packets put OHLC right after the last price.
+const INDEX_HIGH: usize = 8;
+const INDEX_CHANGE: usize = 24;
+const INDEX_EXCHANGE_TIMESTAMP: usize = 28;
+
+/// Zero-copy view of one packet in a binary ticker frame.
+///
+/// Fields are read from the frame bytes on access, so visiting a packet costs
+/// nothing until a field is asked for. Accessors return `None` for fields the
+/// layout does not carry.
+#[derive(Debug, Clone, Copy)]
+pub struct TickPacket<'a> {
+    bytes: &'a [u8],
+    layout: PacketLayout,
+    divisor: f64,
+}
+
+impl<'a> TickPacket<'a> {
+    /// View `bytes` as a packet; `None` when the length matches no known layout.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let layout = PacketLayout::from_len(bytes.len())?;
+        Some(Self {
+            bytes,
+            layout,
+            divisor: price_divisor(u32_at(bytes, 0) as i32),
+        })
+    }
+
+    pub fn layout(&self) -> PacketLayout {
+        self.layout
+    }
+
+    pub fn mode(&self) -> TickMode {
+        self.layout.mode()
+    }
+
+    pub fn instrument_token(&self) -> i32 {
+        self.u32(0) as i32
+    }
+
+    pub fn last_price(&self) -> f64 {
+        self.price(4)
+    }
+
+    pub fn ohlc(&self) -> Option<Ohlc> {
+        match self.layout {
+            PacketLayout::Ltp => None,
+            PacketLayout::IndexQuote | PacketLayout::IndexFull => Some(Ohlc {
+                high: self.price(INDEX_HIGH),
+                low: self.price(INDEX_HIGH + 4),
+                open: self.price(INDEX_HIGH + 8),
+                close: self.price(INDEX_HIGH + 12),
+            }),
+            PacketLayout::Quote | PacketLayout::Full => Some(Ohlc {
+                open: self.price(OPEN),
+                high: self.price(OPEN + 4),
+                low: self.price(OPEN + 8),
+                close: self.price(OPEN + 12),
+            }),
+        }
+    }
+
+    /// Index packets carry the absolute change; for quote/full packets it is
+    /// derived from the close as a fraction.
+    pub fn change(&self) -> Option<f64> {
+        if self.layout.is_index() {
+            return Some(self.price(INDEX_CHANGE));
+        }
+        let close = self.ohlc()?.close;
+        Some(if close != 0.0 {
+            (self.last_price() - close) / close
+        } else {
+            0.0
+        })
+    }
+
+    pub fn last_quantity(&self) -> Option<u32> {
+        self.quote_u32(LAST_QUANTITY)
+    }
+
+    pub fn average_traded_price(&self) -> Option<f64> {
+        self.has_quote().then(|| self.price(AVERAGE_PRICE))
+    }
+
+    pub fn volume_traded(&self) -> Option<u32> {
+        self.quote_u32(VOLUME)
+    }
+
+    pub fn total_buy_quantity(&self) -> Option<u32> {
+        self.quote_u32(BUY_QUANTITY)
+    }
+
+    pub fn total_sell_quantity(&self) -> Option<u32> {
+        self.quote_u32(SELL_QUANTITY)
+    }
+
+    pub fn last_trade_time(&self) -> Option<u32> {
+        self.full_u32(LAST_TRADE_TIME)
+    }
+
+    pub fn open_interest(&self) -> Option<u32> {
+        self.full_u32(OPEN_INTEREST)
+    }
+
+    pub fn oi_day_high(&self) -> Option<u32> {
+        self.full_u32(OI_DAY_HIGH)
+    }
+
+    pub fn oi_day_low(&self) -> Option<u32> {
+        self.full_u32(OI_DAY_LOW)
+    }
+
+    pub fn exchange_timestamp(&self) -> Option<u32> {
+        match self.layout {
+            PacketLayout::IndexFull => Some(self.u32(INDEX_EXCHANGE_TIMESTAMP)),
+            PacketLayout::Full => Some(self.u32(EXCHANGE_TIMESTAMP)),
+            _ => None,
+        }
+    }
+
+    /// Depth level `level` (0..5) on the buy or sell side; full packets only.
+    pub fn depth_level(&self, sell: bool, level: usize) -> Option<DepthLevel> {
+        (self.layout == PacketLayout::Full && level < 5).then(|| self.level_at(sell, level))
+    }
+
+    pub fn depth(&self) -> Option<MarketDepth> {
+        (self.layout == PacketLayout::Full).then(|| MarketDepth {
+            buy: std::array::from_fn(|i| self.level_at(false, i)),
+            sell: std::array::from_fn(|i| self.level_at(true, i)),
+        })
+    }
+
+    /// Materialize an owned [`Tick`].
+    pub fn to_tick(&self, received_ns: u64) -> Tick {
+        Tick {
+            instrument_token: self.instrument_token(),
+            mode: self.mode(),
+            last_price: self.last_price(),
+            last_quantity: self.last_quantity(),
+            average_traded_price: self.average_traded_price(),
+            volume_traded: self.volume_traded(),
+            total_buy_quantity: self.total_buy_quantity(),
+            total_sell_quantity: self.total_sell_quantity(),
+            ohlc: self.ohlc(),
+            change: self.change(),
+            last_trade_time: self.last_trade_time(),
+            open_interest: self.open_interest(),
+            oi_day_high: self.oi_day_high(),
+            oi_day_low: self.oi_day_low(),
+            exchange_timestamp: self.exchange_timestamp(),
+            depth: self.depth(),
+            received_ns,
+        }
+    }
+
+    /// Overwrite `tick` with this packet's fields, reusing its depth buffer.
+    ///
+    /// Same result as `*tick = self.to_tick(received_ns)` without building a
+    /// second `Tick`; `TickStore::update_packet` uses it to update in place.
+    pub fn write_to(&self, tick: &mut Tick, received_ns: u64) {
+        tick.instrument_token = self.instrument_token();
+        tick.mode = self.mode();
+        tick.last_price = self.last_price();
+        tick.last_quantity = self.last_quantity();
+        tick.average_traded_price = self.average_traded_price();
+        tick.volume_traded = self.volume_traded();
+        tick.total_buy_quantity = self.total_buy_quantity();
+        tick.total_sell_quantity = self.total_sell_quantity();
+        tick.ohlc = self.ohlc();
+        tick.change = self.change();
+        tick.last_trade_time = self.last_trade_time();
+        tick.open_interest = self.open_interest();
+        tick.oi_day_high = self.oi_day_high();
+        tick.oi_day_low = self.oi_day_low();
+        tick.exchange_timestamp = self.exchange_timestamp();
+        match (self.layout, tick.depth.as_mut()) {
+            (PacketLayout::Full, Some(depth)) => {
+                for level in 0..5 {
+                    depth.buy[level] = self.level_at(false, level);
+                    depth.sell[level] = self.level_at(true, level);
+                }
+            }
+            _ => tick.depth = self.depth(),
+        }
+        tick.received_ns = received_ns;
+    }
+
+    fn has_quote(&self) -> bool {
+        matches!(self.layout, PacketLayout::Quote | PacketLayout::Full)
+    }
+
+    fn quote_u32(&self, at: usize) -> Option<u32> {
+        self.has_quote().then(|| self.u32(at))
+    }
+
+    fn full_u32(&self, at: usize) -> Option<u32> {
+        (self.layout == PacketLayout::Full).then(|| self.u32(at))
+    }
+
+    fn level_at(&self, sell: bool, level: usize) -> DepthLevel {
+        let at = DEPTH + (usize::from(sell) * 5 + level) * DEPTH_LEVEL_LEN;
+        DepthLevel {
+            quantity: self.u32(at),
+            price: self.price(at + 4),
+            orders: u16::from_be_bytes([self.bytes[at + 8], self.bytes[at + 9]]),
+        }
+    }
+
+    // Offsets are within bounds: `parse` only accepts exact layout lengths.
+    fn u32(&self, at: usize) -> u32 {
+        u32_at(self.bytes, at)
+    }
+
+    fn price(&self, at: usize) -> f64 {
+        self.u32(at) as i32 as f64 / self.divisor
+    }
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+/// Walk a binary ticker frame and hand each known packet to `visit`, without
+/// allocating.
+///
+/// This is the hot-path counterpart of [`super::decode_binary_ticks`]: the
+/// caller reads only the fields it needs from each [`TickPacket`] (or calls
+/// [`TickPacket::to_tick`]). Unknown layouts are skipped and reported in the
+/// returned [`FrameStats`]; a truncated frame stops the walk.
+pub fn visit_binary_ticks<'a>(
+    payload: &'a [u8],
+    mut visit: impl FnMut(TickPacket<'a>),
+) -> FrameStats {
+    let mut stats = FrameStats::default();
+    if payload.len() < 2 {
+        return stats;
+    }
+    let n_packets = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+    let mut offset = 2usize;
+
+    for _ in 0..n_packets {
+        if offset + 2 > payload.len() {
+            break;
+        }
+        let packet_len = u16::from_be_bytes([payload[offset], payload[offset + 1]]) as usize;
+        offset += 2;
+        if offset + packet_len > payload.len() {
+            break;
+        }
+        let packet = &payload[offset..offset + packet_len];
+        offset += packet_len;
+        stats.packets += 1;
+
+        match TickPacket::parse(packet) {
+            Some(p) => visit(p),
+            None => {
+                stats.unknown_length += 1;
+                stats.first_unknown.get_or_insert(UnknownPacket {
+                    len: packet_len,
+                    instrument_token: (packet_len >= 4).then(|| u32_at(packet, 0) as i32),
+                });
+            }
+        }
+    }
+
+    stats
+}
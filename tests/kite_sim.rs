@@ -0,0 +1,185 @@
+//! End-to-end tests against the offline simulator (`src/bin/kite-sim`): the
+//! REST client and the ticker websocket run unchanged, pointed at kite-sim
+//! through `KITE_API_BASE_URL` / `KITE_WS_URL`.
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zatamap_trade_rust::core::KiteEndpoints;
+use zatamap_trade_rust::kite::auth::verify_session;
+use zatamap_trade_rust::kite::client::KiteClient;
+use zatamap_trade_rust::kite::ws::{KiteTickerWs, TickLogConfig, TokenRefresher};
+use zatamap_trade_rust::ticks::{TickMode, TickStore};
+
+const API_KEY: &str = "sim-api-key";
+const ACCESS_TOKEN: &str = "sim-access-token";
+const NIFTY_50: i32 = 256265;
+const INFY: i32 = 408065;
+
+/// A kite-sim process, killed on drop.
+struct Sim {
+    child: Child,
+    addr: SocketAddr,
+}
+
+impl Sim {
+    /// Start kite-sim on a free local port and wait until it accepts connections.
+    fn start() -> Self {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("reserve a local port");
+        let child = Command::new(env!("CARGO_BIN_EXE_kite-sim"))
+            .env("KITE_SIM_ADDR", addr.to_string())
+            .env(
+                "KITE_SIM_FIXTURES",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/kite-sim"),
+            )
+            .env("KITE_SIM_ACCESS_TOKEN", ACCESS_TOKEN)
+            .env("KITE_SIM_TICK_INTERVAL_MS", "50")
+            .env("RUST_LOG", "warn")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("spawn kite-sim");
+        let mut sim = Self { child, addr };
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(sim.addr).is_err() {
+            if let Ok(Some(status)) = sim.child.try_wait() {
+                panic!("kite-sim exited early: {status}");
+            }
+            assert!(
+                Instant::now() < deadline,
+                "kite-sim did not start on {addr}"
+            );
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        sim
+    }
+}
+
+impl Drop for Sim {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn ticks_from_the_simulator_reach_the_store() {
+    let sim = Sim::start();
+    std::env::set_var("KITE_API_BASE_URL", format!("http://{}", sim.addr));
+    std::env::set_var("KITE_WS_URL", format!("ws://{}/ws", sim.addr));
+    let endpoints = KiteEndpoints::from_env();
+
+    // REST preflight, as run_ticker does: the good token works, others are token errors.
+    let kite = KiteClient::with_base_url(API_KEY, ACCESS_TOKEN, &endpoints.api_base_url).unwrap();
+    let profile = kite.profile().await.unwrap();
+    assert_eq!(profile.user_id.as_deref(), Some("SIM001"));
+    let stale = KiteClient::with_base_url(API_KEY, "expired", &endpoints.api_base_url).unwrap();
+    assert!(stale.profile().await.unwrap_err().is_kite_token_error());
+
+    let store = Arc::new(TickStore::default());
+    let log = TickLogConfig {
+        enabled: false,
+        interval: Duration::from_secs(60),
+    };
+    let ws = KiteTickerWs::new(
+        endpoints.ws_url,
+        API_KEY.to_string(),
+        ACCESS_TOKEN.to_string(),
+        vec![NIFTY_50],
+        store.clone(),
+        log,
+    );
+    let control = ws.control();
+    let task = ws.spawn();
+    control.subscribe(vec![INFY], TickMode::Ltp).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let (nifty, infy) = loop {
+        let tick = |token| store.get_state(token).and_then(|s| s.last_tick);
+        if let (Some(nifty), Some(infy)) = (tick(NIFTY_50), tick(INFY)) {
+            break (nifty, infy);
+        }
+        assert!(
+            Instant::now() < deadline,
+            "no ticks from kite-sim: {:?}",
+            control.stats()
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    };
+    task.abort();
+
+    assert_eq!(nifty.mode, TickMode::Full);
+    assert_eq!(infy.mode, TickMode::Ltp);
+    // Prices start at the fixture's last_price and random-walk from there.
+    assert!(
+        (nifty.last_price - 23465.6).abs() < 23465.6 * 0.1,
+        "{nifty:?}"
+    );
+    assert!(infy.last_price > 0.0, "{infy:?}");
+    assert!(control.health().connected);
+}
+
+/// Refresher that hands out `next` and records the tokens it was asked to replace.
+fn refresher(next: Option<&str>) -> (TokenRefresher, Arc<Mutex<Vec<String>>>) {
+    let rejected = Arc::new(Mutex::new(Vec::new()));
+    let calls = rejected.clone();
+    let next = next.map(str::to_string);
+    let refresher: TokenRefresher = Arc::new(move |token: String| {
+        calls.lock().unwrap().push(token);
+        let next = next.clone();
+        Box::pin(async move { Ok(next) })
+    });
+    (refresher, rejected)
+}
+
+#[tokio::test]
+async fn preflight_replaces_an_expired_token() {
+    let sim = Sim::start();
+    let base_url = format!("http://{}", sim.addr);
+
+    let (fresh, rejected) = refresher(Some(ACCESS_TOKEN));
+    let (kite, token) = verify_session(&base_url, API_KEY, "expired".to_string(), Some(&fresh))
+        .await
+        .unwrap();
+    assert_eq!(token, ACCESS_TOKEN);
+    assert_eq!(*rejected.lock().unwrap(), ["expired"]);
+    assert!(kite.profile().await.is_ok());
+
+    // A valid token is not refreshed.
+    let (_, token) = verify_session(&base_url, API_KEY, ACCESS_TOKEN.to_string(), Some(&fresh))
+        .await
+        .unwrap();
+    assert_eq!(token, ACCESS_TOKEN);
+    assert_eq!(rejected.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn preflight_fails_with_a_token_error_when_no_fresh_token_is_available() {
+    let sim = Sim::start();
+    let base_url = format!("http://{}", sim.addr);
+
+    let (none, rejected) = refresher(None);
+    let Err(err) = verify_session(&base_url, API_KEY, "expired".to_string(), Some(&none)).await
+    else {
+        panic!("expired token accepted");
+    };
+    assert!(err.is_kite_token_error(), "{err}");
+    assert_eq!(rejected.lock().unwrap().len(), 1);
+
+    // A refresher that hands back a token Kite already rejected ends the retries.
+    let (stale, rejected) = refresher(Some("also-expired"));
+    let Err(err) = verify_session(&base_url, API_KEY, "expired".to_string(), Some(&stale)).await
+    else {
+        panic!("expired token accepted");
+    };
+    assert!(err.is_kite_token_error(), "{err}");
+    assert_eq!(*rejected.lock().unwrap(), ["expired", "also-expired"]);
+
+    let Err(err) = verify_session(&base_url, API_KEY, "expired".to_string(), None).await else {
+        panic!("expired token accepted");
+    };
+    assert!(err.is_kite_token_error(), "{err}");
+}
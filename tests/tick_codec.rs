@@ -0,0 +1,267 @@
+//! Round-trip and fuzz tests for the binary tick codec (`ticks::encode` and
+//! `decode_binary_ticks` / `visit_binary_ticks`).
+
+use proptest::prelude::*;
+use zatamap_trade_rust::ticks::encode::{encode_frame, layout_for};
+use zatamap_trade_rust::ticks::{
+    decode_binary_ticks, decode_binary_ticks_with_stats, price_divisor, visit_binary_ticks,
+    DepthLevel, MarketDepth, Ohlc, PacketLayout, Tick, TickMode,
+};
+
+const RECEIVED_NS: u64 = 1_718_000_000_123_456_789;
+
+fn mode() -> impl Strategy<Value = TickMode> {
+    prop_oneof![
+        Just(TickMode::Ltp),
+        Just(TickMode::Quote),
+        Just(TickMode::Full)
+    ]
+}
+
+/// Instrument token across all segments (1..=9 in the low byte).
+fn token() -> impl Strategy<Value = i32> {
+    (0i32..(1 << 22), 1i32..=9).prop_map(|(exchange_token, seg)| (exchange_token << 8) | seg)
+}
+
+/// Price that is exactly representable on the wire for `token`.
+fn price(token: i32) -> impl Strategy<Value = f64> {
+    let divisor = price_divisor(token);
+    any::<i32>().prop_map(move |raw| raw as f64 / divisor)
+}
+
+fn depth(token: i32) -> impl Strategy<Value = MarketDepth> {
+    let level = (any::<u32>(), price(token), any::<u16>()).prop_map(|(quantity, price, orders)| {
+        DepthLevel {
+            quantity,
+            price,
+            orders,
+        }
+    });
+    proptest::collection::vec(level, 10).prop_map(|l| MarketDepth {
+        buy: [l[0], l[1], l[2], l[3], l[4]],
+        sell: [l[5], l[6], l[7], l[8], l[9]],
+    })
+}
+
+/// A tick with every field set; the encoder writes what its layout carries.
+fn tick() -> impl Strategy<Value = Tick> {
+    (token(), mode()).prop_flat_map(|(token, mode)| {
+        (
+            (
+                price(token),
+                price(token),
+                price(token),
+                price(token),
+                price(token),
+            ),
+            (price(token), price(token)),
+            proptest::array::uniform4(any::<u32>()),
+            proptest::array::uniform5(any::<u32>()),
+            depth(token),
+        )
+            .prop_map(
+                move |((last_price, open, high, low, close), (avg, change), q, t, depth)| Tick {
+                    instrument_token: token,
+                    mode,
+                    last_price,
+                    last_quantity: Some(q[0]),
+                    average_traded_price: Some(avg),
+                    volume_traded: Some(q[1]),
+                    total_buy_quantity: Some(q[2]),
+                    total_sell_quantity: Some(q[3]),
+                    ohlc: Some(Ohlc {
+                        open,
+                        high,
+                        low,
+                        close,
+                    }),
+                    change: Some(change),
+                    last_trade_time: Some(t[0]),
+                    open_interest: Some(t[1]),
+                    oi_day_high: Some(t[2]),
+                    oi_day_low: Some(t[3]),
+                    exchange_timestamp: Some(t[4]),
+                    depth: Some(depth),
+                    received_ns: RECEIVED_NS,
+                },
+            )
+    })
+}
+
+/// What decoding the encoded `t` must yield: only the fields its layout
+/// carries, with `change` derived from the close for quote/full packets.
+fn expected(t: &Tick) -> Tick {
+    let layout = layout_for(t);
+    let mut e = Tick::new_ltp(t.instrument_token, t.last_price, t.received_ns);
+    if layout == PacketLayout::Ltp {
+        return e;
+    }
+    e.mode = t.mode;
+    e.ohlc = t.ohlc;
+    if matches!(layout, PacketLayout::IndexQuote | PacketLayout::IndexFull) {
+        e.change = t.change;
+        if layout == PacketLayout::IndexFull {
+            e.exchange_timestamp = t.exchange_timestamp;
+        }
+        return e;
+    }
+    let close = t.ohlc.map(|o| o.close).unwrap_or(0.0);
+    e.change = Some(if close != 0.0 {
+        (t.last_price - close) / close
+    } else {
+        0.0
+    });
+    e.last_quantity = t.last_quantity;
+    e.average_traded_price = t.average_traded_price;
+    e.volume_traded = t.volume_traded;
+    e.total_buy_quantity = t.total_buy_quantity;
+    e.total_sell_quantity = t.total_sell_quantity;
+    if layout == PacketLayout::Full {
+        e.last_trade_time = t.last_trade_time;
+        e.open_interest = t.open_interest;
+        e.oi_day_high = t.oi_day_high;
+        e.oi_day_low = t.oi_day_low;
+        e.exchange_timestamp = t.exchange_timestamp;
+        e.depth = t.depth.clone();
+    }
+    e
+}
+
+/// `Tick` has no `PartialEq`; `Debug` prints f64s exactly, so compare that.
+fn same(a: &Tick, b: &Tick) -> bool {
+    format!("{a:?}") == format!("{b:?}")
+}
+
+proptest! {
+    #[test]
+    fn frames_round_trip(ticks in proptest::collection::vec(tick(), 0..40)) {
+        let frame = encode_frame(&ticks);
+        let (decoded, stats) = decode_binary_ticks_with_stats(&frame, RECEIVED_NS);
+
+        prop_assert_eq!(stats.packets, ticks.len());
+        prop_assert_eq!(stats.unknown_length, 0);
+        prop_assert_eq!(decoded.len(), ticks.len());
+        for (t, d) in ticks.iter().zip(&decoded) {
+            let e = expected(t);
+            prop_assert!(same(&e, d), "expected {:?}\n got {:?}", e, d);
+        }
+    }
+
+    #[test]
+    fn visitor_agrees_with_decoder(ticks in proptest::collection::vec(tick(), 0..20)) {
+        let frame = encode_frame(&ticks);
+        let decoded = decode_binary_ticks(&frame, RECEIVED_NS);
+        let mut visited = Vec::new();
+        visit_binary_ticks(&frame, |p| visited.push(p.to_tick(RECEIVED_NS)));
+
+        prop_assert_eq!(visited.len(), decoded.len());
+        for (v, d) in visited.iter().zip(&decoded) {
+            prop_assert!(same(v, d));
+        }
+    }
+
+    /// Arbitrary bytes never panic, and packet counts stay consistent.
+    #[test]
+    fn arbitrary_bytes_do_not_panic(payload in proptest::collection::vec(any::<u8>(), 0..2048)) {
+        let (decoded, stats) = decode_binary_ticks_with_stats(&payload, RECEIVED_NS);
+        let declared = payload
+            .get(..2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+            .unwrap_or(0);
+
+        prop_assert!(stats.packets <= declared);
+        prop_assert_eq!(decoded.len() + stats.unknown_length, stats.packets);
+        prop_assert_eq!(stats.first_unknown.is_some(), stats.unknown_length > 0);
+    }
+
+    /// Well-framed packets of random content, mostly at known layout lengths so
+    /// every decoding path sees garbage values.
+    #[test]
+    fn random_packets_do_not_panic(
+        packets in proptest::collection::vec(
+            prop_oneof![
+                4 => prop_oneof![Just(8usize), Just(28), Just(32), Just(44), Just(184)],
+                1 => 0usize..300,
+            ]
+            .prop_flat_map(|len| proptest::collection::vec(any::<u8>(), len)),
+            0..30,
+        ),
+    ) {
+        let mut frame = (packets.len() as u16).to_be_bytes().to_vec();
+        for p in &packets {
+            frame.extend_from_slice(&(p.len() as u16).to_be_bytes());
+            frame.extend_from_slice(p);
+        }
+        let known = packets
+            .iter()
+            .filter(|p| PacketLayout::from_len(p.len()).is_some())
+            .count();
+
+        let (decoded, stats) = decode_binary_ticks_with_stats(&frame, RECEIVED_NS);
+        prop_assert_eq!(stats.packets, packets.len());
+        prop_assert_eq!(decoded.len(), known);
+        prop_assert_eq!(stats.unknown_length, packets.len() - known);
+    }
+
+    /// A frame cut short yields a prefix of the original ticks.
+    #[test]
+    fn truncated_frames_decode_a_prefix(
+        ticks in proptest::collection::vec(tick(), 1..20),
+        cut in any::<proptest::sample::Index>(),
+    ) {
+        let frame = encode_frame(&ticks);
+        let cut = cut.index(frame.len());
+        let full = decode_binary_ticks(&frame, RECEIVED_NS);
+        let (partial, stats) = decode_binary_ticks_with_stats(&frame[..cut], RECEIVED_NS);
+
+        prop_assert!(partial.len() < full.len());
+        prop_assert_eq!(stats.unknown_length, 0);
+        for (p, f) in partial.iter().zip(&full) {
+            prop_assert!(same(p, f));
+        }
+    }
+
+    /// Corrupting bytes of a valid frame (lengths, counts or payload) never panics.
+    #[test]
+    fn corrupted_frames_do_not_panic(
+        ticks in proptest::collection::vec(tick(), 1..20),
+        flips in proptest::collection::vec((any::<proptest::sample::Index>(), any::<u8>()), 1..8),
+    ) {
+        let mut frame = encode_frame(&ticks);
+        for (at, byte) in flips {
+            let i = at.index(frame.len());
+            frame[i] ^= byte;
+        }
+        let (decoded, stats) = decode_binary_ticks_with_stats(&frame, RECEIVED_NS);
+        prop_assert_eq!(decoded.len() + stats.unknown_length, stats.packets);
+    }
+}
+
+#[test]
+fn encoder_picks_kite_packet_lengths() {
+    let mut t = Tick::new_ltp(256265, 23465.6, 0);
+    let packet_len = |t: &Tick| {
+        let frame = encode_frame([t]);
+        u16::from_be_bytes([frame[2], frame[3]]) as usize
+    };
+
+    assert_eq!(packet_len(&t), 8);
+    t.mode = TickMode::Quote;
+    assert_eq!(packet_len(&t), 28);
+    t.mode = TickMode::Full;
+    assert_eq!(packet_len(&t), 32);
+
+    t.instrument_token = 408065;
+    t.mode = TickMode::Quote;
+    assert_eq!(packet_len(&t), 44);
+    t.mode = TickMode::Full;
+    assert_eq!(packet_len(&t), 184);
+}
+
+#[test]
+fn empty_and_heartbeat_frames_decode_to_nothing() {
+    assert_eq!(encode_frame(&[]), vec![0, 0]);
+    assert!(decode_binary_ticks(&[], 0).is_empty());
+    assert!(decode_binary_ticks(&[0], 0).is_empty());
+    assert!(decode_binary_ticks(&[0, 0], 0).is_empty());
+}